@@ -0,0 +1,57 @@
+use crate::components::{Hidden, Position, Renderable};
+use crate::map::{tile_glyph, Map};
+use rltk::{Point, Rltk};
+use specs::prelude::*;
+
+/// The part of the console the map is drawn in; the rows below it belong to the GUI.
+pub const VIEW_WIDTH: i32 = 80;
+pub const VIEW_HEIGHT: i32 = 43;
+
+/// World coordinates of the viewport as `(min_x, max_x, min_y, max_y)`, centred on the player.
+/// A tile at world `(x, y)` is drawn at screen `(x - min_x, y - min_y)`.
+pub fn get_screen_bounds(ecs: &World) -> (i32, i32, i32, i32) {
+    let player_pos = ecs.fetch::<Point>();
+    let min_x = player_pos.x - VIEW_WIDTH / 2;
+    let min_y = player_pos.y - VIEW_HEIGHT / 2;
+    (min_x, min_x + VIEW_WIDTH, min_y, min_y + VIEW_HEIGHT)
+}
+
+/// Draws the part of the map around the player, and the entities on it.
+pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    let (min_x, max_x, min_y, max_y) = get_screen_bounds(ecs);
+    draw_tiles(&map, ctx, min_x, min_y);
+
+    let positions = ecs.read_storage::<Position>();
+    let renderables = ecs.read_storage::<Renderable>();
+    let hidden = ecs.read_storage::<Hidden>();
+    let mut data = (&positions, &renderables, !&hidden).join().collect::<Vec<_>>();
+    data.sort_by_key(|&a| std::cmp::Reverse(a.1.render_order));
+    for (pos, render, _hidden) in data.iter() {
+        if pos.x < min_x || pos.x >= max_x || pos.y < min_y || pos.y >= max_y { continue; }
+        let idx = map.xy_idx(pos.x, pos.y);
+        if map.visible_tiles[idx] { ctx.set(pos.x - min_x, pos.y - min_y, render.fg, render.bg, render.glyph) }
+    }
+}
+
+/// Draws a map with no player on it, centred on the middle of the map. Used by the generation
+/// visualizer.
+pub fn render_debug_map(map: &Map, ctx: &mut Rltk) {
+    draw_tiles(map, ctx, map.width / 2 - VIEW_WIDTH / 2, map.height / 2 - VIEW_HEIGHT / 2);
+}
+
+fn draw_tiles(map: &Map, ctx: &mut Rltk, min_x: i32, min_y: i32) {
+    for screen_y in 0..VIEW_HEIGHT {
+        for screen_x in 0..VIEW_WIDTH {
+            let x = screen_x + min_x;
+            let y = screen_y + min_y;
+            if x < 0 || x >= map.width || y < 0 || y >= map.height { continue; }
+
+            let idx = map.xy_idx(x, y);
+            if map.revealed_tiles[idx] {
+                let (glyph, fg, bg) = tile_glyph(idx, map);
+                ctx.set(screen_x, screen_y, fg, bg, glyph);
+            }
+        }
+    }
+}
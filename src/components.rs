@@ -3,27 +3,74 @@ use specs_derive::*;
 use rltk::{RGB};
 use serde::{Serialize, Deserialize};
 use specs::saveload::{Marker, ConvertSaveload};
-use specs::error::NoError;
+use std::convert::Infallible as NoError;
+
+/// What a ranged weapon shoots.
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum AmmoKind {
+    Arrow,
+    Bolt,
+}
+
+impl AmmoKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AmmoKind::Arrow => "arrows",
+            AmmoKind::Bolt => "bolts",
+        }
+    }
+}
+
+/// A stack of `count` shots for weapons that take `kind`. Picking up more of the same adds to the
+/// stack already in the backpack.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Ammunition {
+    pub kind: AmmoKind,
+    pub count: i32,
+}
 
 #[derive(Component, ConvertSaveload, Debug, Clone)]
 pub struct AreaOfEffect {
     pub radius: i32,
 }
 
+/// The raw abilities a creature is born with. 10 is average; every two points either side of it
+/// is worth +/-1 to whatever the attribute feeds into.
+#[derive(Component, Serialize, Deserialize, Debug, Clone)]
+pub struct Attributes {
+    /// Hitting things, and hitting them hard.
+    pub might: i32,
+    /// Toughness; adds to the hp gained each level.
+    pub fitness: i32,
+    /// Getting out of the way, which makes a creature harder to hit, acting sooner and aiming.
+    pub quickness: i32,
+    /// Getting more out of magic items.
+    pub intelligence: i32,
+}
+
+impl Attributes {
+    /// The modifier an attribute of `value` adds to rolls.
+    pub fn bonus(value: i32) -> i32 {
+        (value - 10) / 2
+    }
+}
+
 #[derive(Component, Serialize, Deserialize, Debug, Clone)]
 pub struct BlocksTile {}
 
+#[derive(Component, Serialize, Deserialize, Debug, Clone)]
+pub struct BlocksVisibility {}
+
 #[derive(Component, ConvertSaveload, Debug, Clone)]
 pub struct CombatStats {
     pub max_hp: i32,
     pub hp: i32,
-    pub defense: i32,
-    pub power: i32,
 }
 
-#[derive(Component, ConvertSaveload, Debug, Clone)]
-pub struct Confusion {
-    pub turns: i32,
+/// Puts a status effect on whoever it is used on, or whoever triggers it.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct CausesStatus {
+    pub effect: StatusEffect,
 }
 
 #[derive(Component, Serialize, Deserialize, Debug, Clone)]
@@ -34,6 +81,19 @@ pub struct DefenseBonus {
     pub defense: i32,
 }
 
+#[derive(Component, Serialize, Deserialize, Debug, Clone)]
+pub struct Door {
+    pub open: bool,
+}
+
+/// Set on anything that moved this turn, so entry triggers know to check its new tile.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct EntityMoved {}
+
+/// Fires when something steps onto this entity's tile.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct EntryTrigger {}
+
 #[derive(Component, ConvertSaveload, Clone)]
 pub struct Equipped {
     pub owner: Entity,
@@ -44,6 +104,7 @@ pub struct Equipped {
 pub enum EquipmentSlot {
     Melee,
     Shield,
+    Ranged,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -51,6 +112,63 @@ pub struct Equippable {
     pub slot: EquipmentSlot,
 }
 
+/// Character level, and the experience earned towards the next one.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+}
+
+impl Experience {
+    /// Experience needed to go from the current level to the next.
+    pub fn xp_to_next_level(&self) -> i32 {
+        self.level * 100
+    }
+
+    pub fn can_level_up(&self) -> bool {
+        self.xp >= self.xp_to_next_level()
+    }
+}
+
+/// Experience awarded to whoever lands the killing blow.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct GrantsExperience {
+    pub xp: i32,
+}
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum HungerState {
+    WellFed,
+    Normal,
+    Hungry,
+    Starving,
+}
+
+/// Counts down the turns left in the current hunger state, then moves on to the next.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct HungerClock {
+    pub state: HungerState,
+    pub duration: i32,
+}
+
+/// Not drawn or named until the player finds it.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Hidden {}
+
+/// A trap the monsters have seen go off. What the player has found out doesn't count; monsters
+/// share what they learn, and path around these from then on.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct KnownToMonsters {}
+
+/// How quickly something acts. Every tick of the clock adds `speed` (adjusted for quickness,
+/// equipment and status effects) to `energy`, and whoever gets to
+/// `systems::initiative::ACTION_COST` first goes next.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Initiative {
+    pub speed: i32,
+    pub energy: i32,
+}
+
 #[derive(Component, ConvertSaveload, Debug, Clone)]
 pub struct InBackpack {
     pub owner: Entity,
@@ -64,19 +182,36 @@ pub struct InflictsDamage {
 #[derive(Component, Serialize, Deserialize, Debug, Clone)]
 pub struct Item {}
 
-#[derive(Component, ConvertSaveload, Clone)]
-pub struct MeleePowerBonus {
-    pub power: i32,
+/// A weapon's damage roll, `damage_n_dice`d`damage_die_type` + `damage_bonus`, and how much it
+/// adds to the wielder's to-hit roll.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct MeleeWeapon {
+    pub damage_n_dice: i32,
+    pub damage_die_type: i32,
+    pub damage_bonus: i32,
+    pub hit_bonus: i32,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Monster {}
 
+/// Set on everything whose turn has come up; taken away again once it has acted.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct MyTurn {}
+
 #[derive(Component, ConvertSaveload, Debug, Clone)]
 pub struct Name {
     pub name: String,
 }
 
+/// Replaces `Position` on entities left behind on another level, see `dungeon::freeze_level_entities`.
+#[derive(Component, ConvertSaveload, Clone)]
+pub struct OtherLevelPosition {
+    pub x: i32,
+    pub y: i32,
+    pub depth: i32,
+}
+
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct ParticleLifetime {
     pub lifetime_ms: f32,
@@ -85,12 +220,18 @@ pub struct ParticleLifetime {
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Player {}
 
-#[derive(Component, ConvertSaveload)]
+#[derive(Component, ConvertSaveload, Clone)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
 }
 
+/// Eating it leaves you well fed for `turns` turns.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct ProvidesFood {
+    pub turns: i32,
+}
+
 #[derive(Component, ConvertSaveload, Debug, Clone)]
 pub struct ProvidesHealing {
     pub heal_amount: i32,
@@ -101,6 +242,18 @@ pub struct Ranged {
     pub range: i32,
 }
 
+/// A bow or crossbow: shoots `ammo` up to `range` tiles, with the damage roll and to-hit bonus
+/// of a `MeleeWeapon`.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct RangedWeapon {
+    pub range: i32,
+    pub ammo: AmmoKind,
+    pub damage_n_dice: i32,
+    pub damage_die_type: i32,
+    pub damage_bonus: i32,
+    pub hit_bonus: i32,
+}
+
 #[derive(Component, ConvertSaveload)]
 pub struct Renderable {
     pub glyph: rltk::FontCharType,
@@ -109,22 +262,164 @@ pub struct Renderable {
     pub render_order: i32,
 }
 
-#[derive(Component, ConvertSaveload, Debug, Clone)]
+/// Heavy equipment that slows down whoever has it equipped.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct SpeedPenalty {
+    pub penalty: i32,
+}
+
+/// Trained ability, added straight on to the relevant rolls.
+#[derive(Component, Serialize, Deserialize, Debug, Clone)]
+pub struct Skills {
+    pub melee: i32,
+    pub ranged: i32,
+    pub defense: i32,
+    pub magic: i32,
+}
+
+/// Destroyed after triggering once.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct SingleActivation {}
+
+/// Damage waiting to be applied this turn, each hit paired with whoever dealt it (if anyone).
+/// It never outlives the turn, so it is not saved.
+#[derive(Component, Debug, Clone)]
 pub struct SufferDamage {
-    pub amount: Vec<i32>,
+    pub amount: Vec<(i32, Option<Entity>)>,
 }
 
 impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32) {
+    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32, attacker: Option<Entity>) {
         if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push(amount);
+            suffering.amount.push((amount, attacker));
         } else {
-            let dmg = SufferDamage { amount: vec![amount] };
+            let dmg = SufferDamage { amount: vec![(amount, attacker)] };
             store.insert(victim, dmg).expect("Unable to insert damage");
         }
     }
 }
 
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// Monsters lose their turns; the player stumbles about at random.
+    Confusion,
+    /// `magnitude` damage every turn.
+    Poison,
+    /// Loses every turn.
+    Stun,
+    /// Acts twice as often.
+    Haste,
+    /// Acts half as often.
+    Slow,
+    /// Heals `magnitude` hp every turn.
+    Regeneration,
+}
+
+impl StatusKind {
+    /// How the effect reads on the status line: "Poisoned", "Hasted", ...
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusKind::Confusion => "Confused",
+            StatusKind::Poison => "Poisoned",
+            StatusKind::Stun => "Stunned",
+            StatusKind::Haste => "Hasted",
+            StatusKind::Slow => "Slowed",
+            StatusKind::Regeneration => "Regenerating",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub turns: i32,
+    pub magnitude: i32,
+}
+
+/// Every status effect currently on an entity, each counting down to expiry.
+#[derive(Component, Serialize, Deserialize, Clone, Default)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    /// Puts `effect` on `target`, starting a new effect list if it has none.
+    pub fn apply(store: &mut WriteStorage<StatusEffects>, target: Entity, effect: StatusEffect) {
+        if let Some(effects) = store.get_mut(target) {
+            effects.add(effect);
+        } else {
+            let mut effects = StatusEffects::default();
+            effects.add(effect);
+            store.insert(target, effects).expect("Unable to insert status effects");
+        }
+    }
+
+    /// Adds an effect. Poison and regeneration stack, adding their magnitudes and keeping the
+    /// longer duration; anything else already present just has its duration topped up. Haste and
+    /// slow cancel each other out.
+    pub fn add(&mut self, effect: StatusEffect) {
+        let opposite = match effect.kind {
+            StatusKind::Haste => Some(StatusKind::Slow),
+            StatusKind::Slow => Some(StatusKind::Haste),
+            _ => None,
+        };
+        if let Some(opposite) = opposite {
+            if self.has(opposite) {
+                self.effects.retain(|e| e.kind != opposite);
+                return;
+            }
+        }
+
+        match self.effects.iter_mut().find(|e| e.kind == effect.kind) {
+            Some(existing) => {
+                if matches!(effect.kind, StatusKind::Poison | StatusKind::Regeneration) {
+                    existing.magnitude += effect.magnitude;
+                }
+                existing.turns = i32::max(existing.turns, effect.turns);
+            }
+            None => self.effects.push(effect),
+        }
+    }
+
+    pub fn get(&self, kind: StatusKind) -> Option<&StatusEffect> {
+        self.effects.iter().find(|e| e.kind == kind)
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.get(kind).is_some()
+    }
+
+    /// True if the effects cost the entity its action this turn.
+    pub fn loses_turn(&self) -> bool {
+        self.has(StatusKind::Stun)
+    }
+
+    /// Takes a turn off every effect and drops the ones that have run out, returning what they were.
+    pub fn count_down(&mut self) -> Vec<StatusKind> {
+        for effect in self.effects.iter_mut() {
+            effect.turns -= 1;
+        }
+        let expired = self.effects.iter().filter(|e| e.turns < 1).map(|e| e.kind).collect();
+        self.effects.retain(|e| e.turns > 0);
+        expired
+    }
+
+    /// Scales a speed for haste and slow.
+    pub fn modify_speed(&self, speed: i32) -> i32 {
+        if self.has(StatusKind::Haste) {
+            speed * 2
+        } else if self.has(StatusKind::Slow) {
+            speed / 2
+        } else {
+            speed
+        }
+    }
+}
+
+/// Sends whatever triggers it to a random spot on the level.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct TeleportsVictim {}
+
 #[derive(Component, ConvertSaveload)]
 pub struct Viewshed {
     pub visible_tiles: Vec<rltk::Point>,
@@ -148,6 +443,11 @@ pub struct WantsToMelee {
     pub target: Entity,
 }
 
+#[derive(Component, ConvertSaveload, Debug, Clone)]
+pub struct WantsToShoot {
+    pub target: rltk::Point,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct WantsToRemoveItem {
     pub item: Entity,
@@ -168,4 +468,75 @@ pub struct SerializeMe;
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct SerializationHelper {
     pub map: super::map::Map,
+    pub dungeon_master: super::dungeon::MasterDungeonMap,
+    pub seed: u64,
+    /// Where the seeded RNG had got to, so a loaded game carries on with the same rolls.
+    pub rng: rltk::RandomNumberGenerator,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effect(kind: StatusKind, turns: i32, magnitude: i32) -> StatusEffect {
+        StatusEffect { kind, turns, magnitude }
+    }
+
+    #[test]
+    fn poison_and_regeneration_stack() {
+        let mut status = StatusEffects::default();
+        status.add(effect(StatusKind::Poison, 3, 2));
+        status.add(effect(StatusKind::Poison, 5, 1));
+        status.add(effect(StatusKind::Regeneration, 4, 1));
+        status.add(effect(StatusKind::Regeneration, 2, 3));
+
+        let poison = status.get(StatusKind::Poison).unwrap();
+        assert_eq!((poison.turns, poison.magnitude), (5, 3));
+        let regeneration = status.get(StatusKind::Regeneration).unwrap();
+        assert_eq!((regeneration.turns, regeneration.magnitude), (4, 4));
+        assert_eq!(status.effects.len(), 2);
+    }
+
+    #[test]
+    fn other_effects_only_top_up_their_duration() {
+        let mut status = StatusEffects::default();
+        status.add(effect(StatusKind::Confusion, 4, 1));
+        status.add(effect(StatusKind::Confusion, 2, 5));
+        status.add(effect(StatusKind::Stun, 1, 1));
+        status.add(effect(StatusKind::Stun, 3, 1));
+
+        let confusion = status.get(StatusKind::Confusion).unwrap();
+        assert_eq!((confusion.turns, confusion.magnitude), (4, 1));
+        assert_eq!(status.get(StatusKind::Stun).unwrap().turns, 3);
+        assert_eq!(status.effects.len(), 2);
+    }
+
+    #[test]
+    fn haste_and_slow_cancel_out() {
+        let mut status = StatusEffects::default();
+        status.add(effect(StatusKind::Haste, 5, 1));
+        status.add(effect(StatusKind::Slow, 5, 1));
+        assert!(status.effects.is_empty());
+
+        status.add(effect(StatusKind::Slow, 5, 1));
+        assert_eq!(status.modify_speed(10), 5);
+        status.add(effect(StatusKind::Haste, 5, 1));
+        assert_eq!(status.modify_speed(10), 10);
+    }
+
+    #[test]
+    fn effects_expire_when_their_turns_run_out() {
+        let mut status = StatusEffects::default();
+        status.add(effect(StatusKind::Stun, 1, 1));
+        status.add(effect(StatusKind::Poison, 2, 1));
+        assert!(status.loses_turn());
+
+        assert_eq!(status.count_down(), vec![StatusKind::Stun]);
+        assert!(!status.loses_turn());
+        assert_eq!(status.get(StatusKind::Poison).unwrap().turns, 1);
+
+        assert_eq!(status.count_down(), vec![StatusKind::Poison]);
+        assert!(status.effects.is_empty());
+        assert!(status.count_down().is_empty());
+    }
 }
@@ -0,0 +1,69 @@
+use crate::components::{OtherLevelPosition, Player, Position};
+use crate::map::Map;
+use serde::{Serialize, Deserialize};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Every level the player has visited, keyed by depth, so taking the stairs back brings you to
+/// the level exactly as you left it.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct MasterDungeonMap {
+    maps: HashMap<i32, Map>,
+}
+
+impl MasterDungeonMap {
+    pub fn new() -> MasterDungeonMap {
+        MasterDungeonMap { maps: HashMap::new() }
+    }
+
+    pub fn store_map(&mut self, map: &Map) {
+        self.maps.insert(map.depth, map.clone());
+    }
+
+    pub fn get_map(&self, depth: i32) -> Option<Map> {
+        self.maps.get(&depth).cloned()
+    }
+}
+
+/// Moves everything on the current level except the player out of the world, by swapping its
+/// `Position` for an `OtherLevelPosition`. Systems only look at entities with a `Position`, so
+/// frozen entities keep all their state but take no part in the game.
+pub fn freeze_level_entities(ecs: &mut World) {
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+    let players = ecs.read_storage::<Player>();
+    let depth = ecs.fetch::<Map>().depth;
+
+    let mut frozen: Vec<Entity> = Vec::new();
+    for (entity, pos, _player) in (&entities, &positions, !&players).join() {
+        other_level_positions
+            .insert(entity, OtherLevelPosition { x: pos.x, y: pos.y, depth })
+            .expect("Insert fail");
+        frozen.push(entity);
+    }
+
+    for entity in frozen.iter() {
+        positions.remove(*entity);
+    }
+}
+
+/// Brings back the entities that were frozen on the current level.
+pub fn thaw_level_entities(ecs: &mut World) {
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+    let depth = ecs.fetch::<Map>().depth;
+
+    let mut thawed: Vec<Entity> = Vec::new();
+    for (entity, pos) in (&entities, &other_level_positions).join() {
+        if pos.depth == depth {
+            positions.insert(entity, Position { x: pos.x, y: pos.y }).expect("Insert fail");
+            thawed.push(entity);
+        }
+    }
+
+    for entity in thawed.iter() {
+        other_level_positions.remove(*entity);
+    }
+}
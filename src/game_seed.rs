@@ -0,0 +1,5 @@
+/// The seed the current run was started from. Every random decision is drawn from the single
+/// `RandomNumberGenerator` resource seeded with it, so the same seed replays the same dungeon.
+pub struct GameSeed {
+    pub seed: u64,
+}
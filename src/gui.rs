@@ -1,7 +1,10 @@
-use crate::components::{CombatStats, Equipped, InBackpack, Name, Player, Position, Viewshed};
+use crate::components::{Ammunition, CombatStats, Equipped, Experience, Hidden, HungerClock, HungerState, InBackpack, Monster, Name, Player, Position, StatusEffects, StatusKind, Viewshed};
+use crate::camera;
 use crate::game_log::GameLog;
+use crate::game_seed::GameSeed;
 use crate::map::Map;
 use crate::state::State;
+use crate::systems::ranged_combat::line_of_fire;
 
 use rltk::{BTerm, Point, RGB, Rltk, VirtualKeyCode};
 use specs::prelude::*;
@@ -21,7 +24,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         ctx.draw_bar_horizontal(
             28,
             43,
-            51,
+            40,
             stats.hp,
             stats.max_hp,
             RGB::named(rltk::RED),
@@ -29,6 +32,17 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         );
     }
 
+    let hunger = ecs.read_storage::<HungerClock>();
+    let player_entity = ecs.fetch::<Entity>();
+    if let Some(hunger) = hunger.get(*player_entity) {
+        match hunger.state {
+            HungerState::WellFed => ctx.print_color(69, 43, RGB::named(rltk::GREEN), RGB::named(rltk::BLACK), "Well Fed"),
+            HungerState::Normal => ctx.print_color(69, 43, RGB::named(rltk::GREY), RGB::named(rltk::BLACK), "Normal"),
+            HungerState::Hungry => ctx.print_color(69, 43, RGB::named(rltk::ORANGE), RGB::named(rltk::BLACK), "Hungry"),
+            HungerState::Starving => ctx.print_color(69, 43, RGB::named(rltk::RED), RGB::named(rltk::BLACK), "Starving"),
+        }
+    }
+
     print_game_log(ecs, ctx);
 
     let mouse_pos = ctx.mouse_pos();
@@ -39,15 +53,41 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     let map = ecs.fetch::<Map>();
     let depth = format!("Depth: {}", map.depth);
     ctx.print_color(2, 43, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), &depth);
+
+    let mut x = 1;
+    let experience = ecs.read_storage::<Experience>();
+    if let Some(exp) = experience.get(*player_entity) {
+        let level = format!(" Level {}  XP: {} / {} ", exp.level, exp.xp, exp.xp_to_next_level());
+        ctx.print_color(x, 43 + GUI_HEIGHT, RGB::named(rltk::CYAN), RGB::named(rltk::BLACK), &level);
+        x += level.len() as i32 + 1;
+    }
+
+    // Active status effects, with the turns they have left, follow along the bottom border
+    let statuses = ecs.read_storage::<StatusEffects>();
+    if let Some(status) = statuses.get(*player_entity) {
+        for effect in status.effects.iter() {
+            let label = format!(" {} {} ", effect.kind.name(), effect.turns);
+            ctx.print_color(x, 43 + GUI_HEIGHT, status_colour(effect.kind), RGB::named(rltk::BLACK), &label);
+            x += label.len() as i32;
+        }
+    }
+
+    let seed = format!(" Seed: {} ", ecs.fetch::<GameSeed>().seed);
+    ctx.print_color(GUI_WIDTH - 1 - seed.len() as i32, 43 + GUI_HEIGHT, RGB::named(rltk::GREY), RGB::named(rltk::BLACK), &seed);
+}
+
+fn status_colour(kind: StatusKind) -> RGB {
+    match kind {
+        StatusKind::Haste | StatusKind::Regeneration => RGB::named(rltk::GREEN),
+        StatusKind::Confusion | StatusKind::Poison | StatusKind::Stun | StatusKind::Slow => RGB::named(rltk::ORANGE),
+    }
 }
 
 fn print_game_log(ecs: &World, ctx: &mut BTerm) {
     let log = ecs.fetch::<GameLog>();
 
-    let mut y = 44;
-    for s in log.entries.iter().rev() {
-        if y < 49 { ctx.print(2, y, s); }
-        y += 1;
+    for (y, s) in (44..49).zip(log.entries.iter().rev()) {
+        ctx.print(2, y, s);
     }
 }
 
@@ -55,14 +95,18 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     let map = ecs.fetch::<Map>();
     let names = ecs.read_storage::<Name>();
     let positions = ecs.read_storage::<Position>();
+    let hidden = ecs.read_storage::<Hidden>();
 
+    let (min_x, _max_x, min_y, _max_y) = camera::get_screen_bounds(ecs);
     let mouse_pos = ctx.mouse_pos();
-    if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height { return; }
+    if mouse_pos.1 >= camera::VIEW_HEIGHT { return; }
+    let mouse_map_pos = (mouse_pos.0 + min_x, mouse_pos.1 + min_y);
+    if mouse_map_pos.0 < 0 || mouse_map_pos.0 >= map.width || mouse_map_pos.1 < 0 || mouse_map_pos.1 >= map.height { return; }
 
     let mut tooltip: Vec<String> = Vec::new();
-    for (name, position) in (&names, &positions).join() {
+    for (name, position, _hidden) in (&names, &positions, !&hidden).join() {
         let idx = map.xy_idx(position.x, position.y);
-        if position.x == mouse_pos.0 && position.y == mouse_pos.1 && map.visible_tiles[idx] {
+        if position.x == mouse_map_pos.0 && position.y == mouse_map_pos.1 && map.visible_tiles[idx] {
             tooltip.push(name.name.to_string());
         }
     }
@@ -77,29 +121,25 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
         if mouse_pos.0 > 40 {
             let arrow_pos = Point::new(mouse_pos.0 - 2, mouse_pos.1);
             let left_x = mouse_pos.0 - width;
-            let mut y = mouse_pos.1;
-            for s in tooltip.iter() {
+            for (y, s) in (mouse_pos.1..).zip(tooltip.iter()) {
                 ctx.print_color(left_x, y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), s);
                 let padding = (width - s.len() as i32) - 1;
                 for i in 0..padding {
-                    ctx.print_color(arrow_pos.x - i, y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), &" ".to_string());
+                    ctx.print_color(arrow_pos.x - i, y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), " ");
                 }
-                y += 1;
             }
-            ctx.print_color(arrow_pos.x, arrow_pos.y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), &"->".to_string());
+            ctx.print_color(arrow_pos.x, arrow_pos.y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), "->");
         } else {
             let arrow_pos = Point::new(mouse_pos.0 + 1, mouse_pos.1);
             let left_x = mouse_pos.0 + 3;
-            let mut y = mouse_pos.1;
-            for s in tooltip.iter() {
+            for (y, s) in (mouse_pos.1..).zip(tooltip.iter()) {
                 ctx.print_color(left_x + 1, y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), s);
                 let padding = (width - s.len() as i32) - 1;
                 for i in 0..padding {
-                    ctx.print_color(arrow_pos.x + 1 + i, y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), &" ".to_string());
+                    ctx.print_color(arrow_pos.x + 1 + i, y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), " ");
                 }
-                y += 1;
             }
-            ctx.print_color(arrow_pos.x, arrow_pos.y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), &"<-".to_string());
+            ctx.print_color(arrow_pos.x, arrow_pos.y, RGB::named(rltk::WHITE), RGB::named(rltk::GREY), "<-");
         }
     }
 }
@@ -111,29 +151,38 @@ pub enum ItemMenuResult {
     Selected,
 }
 
+/// An item's name as listed in the menus, with the count for stacks of ammunition.
+fn item_label(name: &Name, ammo: Option<&Ammunition>) -> String {
+    match ammo {
+        Some(ammo) => format!("{} ({})", name.name, ammo.count),
+        None => name.name.to_string(),
+    }
+}
+
 pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
     let player_entity = gs.ecs.fetch::<Entity>();
     let names = gs.ecs.read_storage::<Name>();
     let backpack = gs.ecs.read_storage::<InBackpack>();
+    let ammunition = gs.ecs.read_storage::<Ammunition>();
     let entities = gs.ecs.entities();
 
     let inventory = (&backpack, &names).join().filter(|item| item.0.owner == *player_entity);
     let count = inventory.count();
 
-    let mut y = (25 - (count / 2)) as i32;
+    let y = (25 - (count / 2)) as i32;
     ctx.draw_box(15, y - 2, 31, (count + 3) as i32, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK));
     ctx.print_color(18, y - 2, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Inventory");
     ctx.print_color(18, y + count as i32 + 1, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "ESCAPE to cancel");
 
     let mut equippable: Vec<Entity> = Vec::new();
     for (j, (entity, _pack, name)) in (&entities, &backpack, &names).join().filter(|item| item.1.owner == *player_entity).enumerate() {
+        let y = y + j as i32;
         ctx.set(17, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437('('));
         ctx.set(18, y, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), 97 + j as rltk::FontCharType);
         ctx.set(19, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437(')'));
 
-        ctx.print(21, y, &name.name.to_string());
+        ctx.print(21, y, item_label(name, ammunition.get(entity)));
         equippable.push(entity);
-        y += 1;
     }
 
     match ctx.key {
@@ -157,25 +206,26 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     let player_entity = gs.ecs.fetch::<Entity>();
     let names = gs.ecs.read_storage::<Name>();
     let backpack = gs.ecs.read_storage::<InBackpack>();
+    let ammunition = gs.ecs.read_storage::<Ammunition>();
     let entities = gs.ecs.entities();
 
     let inventory = (&backpack, &names).join().filter(|item| item.0.owner == *player_entity);
     let count = inventory.count();
 
-    let mut y = (25 - (count / 2)) as i32;
+    let y = (25 - (count / 2)) as i32;
     ctx.draw_box(15, y - 2, 31, (count + 3) as i32, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK));
     ctx.print_color(18, y - 2, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Drop Which Item?");
     ctx.print_color(18, y + count as i32 + 1, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "ESCAPE to cancel");
 
     let mut equipable: Vec<Entity> = Vec::new();
     for (j, (entity, _pack, name)) in (&entities, &backpack, &names).join().filter(|item| item.1.owner == *player_entity).enumerate() {
+        let y = y + j as i32;
         ctx.set(17, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437('('));
         ctx.set(18, y, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), 97 + j as rltk::FontCharType);
         ctx.set(19, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437(')'));
 
-        ctx.print(21, y, &name.name.to_string());
+        ctx.print(21, y, item_label(name, ammunition.get(entity)));
         equipable.push(entity);
-        y += 1;
     }
 
     match ctx.key {
@@ -204,22 +254,20 @@ pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Opti
     let inventory = (&backpack, &names).join().filter(|item| item.0.owner == *player_entity);
     let count = inventory.count();
 
-    let mut y = (25 - (count / 2)) as i32;
+    let y = (25 - (count / 2)) as i32;
     ctx.draw_box(15, y - 2, 31, (count + 3) as i32, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK));
     ctx.print_color(18, y - 2, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Remove Which Item?");
     ctx.print_color(18, y + count as i32 + 1, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "ESCAPE to cancel");
 
     let mut equippable: Vec<Entity> = Vec::new();
-    let mut j = 0;
-    for (entity, _pack, name) in (&entities, &backpack, &names).join().filter(|item| item.1.owner == *player_entity) {
+    for (j, (entity, _pack, name)) in (&entities, &backpack, &names).join().filter(|item| item.1.owner == *player_entity).enumerate() {
+        let y = y + j as i32;
         ctx.set(17, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437('('));
         ctx.set(18, y, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), 97 + j as rltk::FontCharType);
         ctx.set(19, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437(')'));
 
-        ctx.print(21, y, &name.name.to_string());
+        ctx.print(21, y, name.name.to_string());
         equippable.push(entity);
-        y += 1;
-        j += 1;
     }
 
     match ctx.key {
@@ -239,45 +287,145 @@ pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Opti
     }
 }
 
-pub fn ranged_target(gs: &mut State, ctx: &mut Rltk, range: i32) -> (ItemMenuResult, Option<Point>) {
+/// Where the targeting cursor is, in world coordinates. `None` until targeting starts, so each
+/// new target selection begins on the nearest hostile.
+#[derive(Default)]
+pub struct TargetingCursor {
+    pub position: Option<Point>,
+    /// Where the mouse was last frame; moving it takes the cursor along with it.
+    mouse: (i32, i32),
+}
+
+/// Picks a tile up to `range` away, with the mouse or the keyboard: TAB cycles through the visible
+/// hostiles nearest first, the arrow keys move the cursor, ENTER or a click confirms and ESCAPE
+/// cancels. The path a projectile would take is drawn to the cursor, along with the area a blast of
+/// `radius` would cover.
+pub fn ranged_target(gs: &mut State, ctx: &mut Rltk, range: i32, radius: Option<i32>) -> (ItemMenuResult, Option<Point>) {
     let player_entity = gs.ecs.fetch::<Entity>();
-    let player_pos = gs.ecs.fetch::<Point>();
+    let player_pos = *gs.ecs.fetch::<Point>();
+    let map = gs.ecs.fetch::<Map>();
     let viewsheds = gs.ecs.read_storage::<Viewshed>();
+    let monsters = gs.ecs.read_storage::<Monster>();
+    let positions = gs.ecs.read_storage::<Position>();
+    let mut cursor = gs.ecs.fetch_mut::<TargetingCursor>();
 
-    ctx.print_color(5, 0, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Select Target:");
+    ctx.print_color(5, 0, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Select Target: TAB next, arrows move, ENTER confirm, ESCAPE cancel");
 
-    let mut available_cells = Vec::new();
-    let visible = viewsheds.get(*player_entity);
-    if let Some(visible) = visible {
-        for idx in visible.visible_tiles.iter() {
-            let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *idx);
-            if distance <= range as f32 {
-                ctx.set_bg(idx.x, idx.y, RGB::named(rltk::BLUE));
-                available_cells.push(idx);
-            }
-        }
-    } else {
+    // Cells are in world coordinates, drawing happens in screen coordinates
+    let (min_x, max_x, min_y, max_y) = camera::get_screen_bounds(&gs.ecs);
+    let on_screen = |point: &Point| point.x >= min_x && point.x < max_x && point.y >= min_y && point.y < max_y;
+    let Some(visible) = viewsheds.get(*player_entity) else {
+        cursor.position = None;
         return (ItemMenuResult::Cancel, None);
+    };
+    let available_cells: Vec<Point> = visible.visible_tiles.iter()
+        .filter(|idx| rltk::DistanceAlg::Pythagoras.distance2d(player_pos, **idx) <= range as f32)
+        .copied()
+        .collect();
+    for idx in available_cells.iter().filter(|idx| on_screen(idx)) {
+        ctx.set_bg(idx.x - min_x, idx.y - min_y, RGB::named(rltk::BLUE));
     }
 
+    let mut hostiles: Vec<Point> = (&monsters, &positions).join()
+        .map(|(_monster, pos)| Point::new(pos.x, pos.y))
+        .filter(|pos| available_cells.contains(pos))
+        .collect();
+    hostiles.sort_by(|a, b| {
+        let distance_a = rltk::DistanceAlg::Pythagoras.distance2d(player_pos, *a);
+        let distance_b = rltk::DistanceAlg::Pythagoras.distance2d(player_pos, *b);
+        distance_a.total_cmp(&distance_b)
+    });
+
     let mouse_pos = ctx.mouse_pos();
-    let mut valid_target = false;
-    for idx in available_cells.iter() { if idx.x == mouse_pos.0 && idx.y == mouse_pos.1 { valid_target = true; } }
-    if valid_target {
-        ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::CYAN));
-        if ctx.left_click {
-            return (ItemMenuResult::Selected, Some(Point::new(mouse_pos.0, mouse_pos.1)));
+    let mouse_map_pos = Point::new(mouse_pos.0 + min_x, mouse_pos.1 + min_y);
+    let mouse_on_map = mouse_pos.1 < camera::VIEW_HEIGHT;
+    if cursor.position.is_none() {
+        cursor.position = Some(hostiles.first().copied().unwrap_or(player_pos));
+        cursor.mouse = mouse_pos;
+    }
+    if cursor.mouse != mouse_pos {
+        cursor.mouse = mouse_pos;
+        if mouse_on_map { cursor.position = Some(mouse_map_pos); }
+    }
+    let mut target = cursor.position.unwrap();
+
+    if let Some(key) = ctx.key {
+        match key {
+            VirtualKeyCode::Escape => {
+                cursor.position = None;
+                return (ItemMenuResult::Cancel, None);
+            }
+            VirtualKeyCode::Tab => {
+                if !hostiles.is_empty() {
+                    let next = match hostiles.iter().position(|hostile| *hostile == target) {
+                        Some(current) if ctx.shift => (current + hostiles.len() - 1) % hostiles.len(),
+                        Some(current) => (current + 1) % hostiles.len(),
+                        None => 0,
+                    };
+                    target = hostiles[next];
+                }
+            }
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                if available_cells.contains(&target) {
+                    cursor.position = None;
+                    return (ItemMenuResult::Selected, Some(target));
+                }
+            }
+            _ => {
+                if let Some((delta_x, delta_y)) = cursor_step(key) {
+                    target.x = (target.x + delta_x).clamp(i32::max(min_x, 0), i32::min(max_x, map.width) - 1);
+                    target.y = (target.y + delta_y).clamp(i32::max(min_y, 0), i32::min(max_y, map.height) - 1);
+                }
+            }
         }
-    } else {
-        ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::RED));
-        if ctx.left_click {
-            return (ItemMenuResult::Cancel, None);
+    }
+    cursor.position = Some(target);
+
+    // Clicking a cell out of reach does nothing, rather than throwing the targeting away
+    if ctx.left_click && mouse_on_map && available_cells.contains(&mouse_map_pos) {
+        cursor.position = None;
+        return (ItemMenuResult::Selected, Some(mouse_map_pos));
+    }
+
+    if let Some(radius) = radius {
+        let mut blast_tiles = rltk::field_of_view(target, radius, &*map);
+        blast_tiles.retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1 && on_screen(p));
+        for tile in blast_tiles.iter() {
+            ctx.set_bg(tile.x - min_x, tile.y - min_y, RGB::named(rltk::DARK_ORANGE));
         }
     }
 
+    // The path goes red past the first wall, as nothing fired will get any further
+    let clear_tiles = line_of_fire(&map, player_pos, target).len();
+    for (i, tile) in rltk::line2d(rltk::LineAlg::Bresenham, player_pos, target).iter().skip(1).enumerate() {
+        if *tile == target || !on_screen(tile) { continue; }
+        let colour = if i < clear_tiles { RGB::named(rltk::DARK_CYAN) } else { RGB::named(rltk::DARK_RED) };
+        ctx.set_bg(tile.x - min_x, tile.y - min_y, colour);
+    }
+
+    if on_screen(&target) {
+        let colour = if available_cells.contains(&target) { RGB::named(rltk::CYAN) } else { RGB::named(rltk::RED) };
+        ctx.set_bg(target.x - min_x, target.y - min_y, colour);
+    }
+
     (ItemMenuResult::NoResponse, None)
 }
 
+/// Which way a key moves the targeting cursor, if it's one that does.
+fn cursor_step(key: VirtualKeyCode) -> Option<(i32, i32)> {
+    match key {
+        VirtualKeyCode::Left | VirtualKeyCode::Numpad4 => Some((-1, 0)),
+        VirtualKeyCode::Right | VirtualKeyCode::Numpad6 => Some((1, 0)),
+        VirtualKeyCode::Up | VirtualKeyCode::Numpad8 => Some((0, -1)),
+        VirtualKeyCode::Down | VirtualKeyCode::Numpad2 => Some((0, 1)),
+        VirtualKeyCode::Numpad7 => Some((-1, -1)),
+        VirtualKeyCode::Numpad9 => Some((1, -1)),
+        VirtualKeyCode::Numpad1 => Some((-1, 1)),
+        VirtualKeyCode::Numpad3 => Some((1, 1)),
+        _ => None,
+    }
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum MainMenuSelection {
     NewGame,
@@ -291,6 +439,13 @@ pub enum MainMenuResult {
     Selected { selected: MainMenuSelection },
 }
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum SeedMenuResult {
+    NoSelection { seed: u64 },
+    Selected { seed: u64 },
+    Cancel,
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum GameOverResult { NoSelection, QuitToMenu }
 
@@ -306,3 +461,48 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
         Some(_) => GameOverResult::QuitToMenu,
     }
 }
+
+/// The attribute a player chooses to improve when they gain a level.
+#[derive(PartialEq, Copy, Clone)]
+pub enum LevelUpChoice { Might, Fitness, Quickness, Intelligence }
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum LevelUpResult {
+    NoSelection,
+    Selected { choice: LevelUpChoice },
+}
+
+const LEVEL_UP_OPTIONS: [(LevelUpChoice, &str); 4] = [
+    (LevelUpChoice::Might, "Might (hit harder)"),
+    (LevelUpChoice::Fitness, "Fitness (more hp)"),
+    (LevelUpChoice::Quickness, "Quickness (dodge, speed, aim)"),
+    (LevelUpChoice::Intelligence, "Intelligence (magic)"),
+];
+
+pub fn level_up_menu(ctx: &mut Rltk) -> LevelUpResult {
+    let count = LEVEL_UP_OPTIONS.len() as i32;
+    let y = 25 - count / 2;
+    ctx.draw_box(15, y - 2, 35, count + 3, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK));
+    ctx.print_color(18, y - 2, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Level Up!");
+    ctx.print_color(18, y + count + 1, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Choose an attribute");
+
+    for (j, (_choice, label)) in LEVEL_UP_OPTIONS.iter().enumerate() {
+        let y = y + j as i32;
+        ctx.set(17, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437('('));
+        ctx.set(18, y, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), 97 + j as rltk::FontCharType);
+        ctx.set(19, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), rltk::to_cp437(')'));
+
+        ctx.print(21, y, label);
+    }
+
+    match ctx.key {
+        None => LevelUpResult::NoSelection,
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && selection < count {
+                return LevelUpResult::Selected { choice: LEVEL_UP_OPTIONS[selection as usize].0 };
+            }
+            LevelUpResult::NoSelection
+        }
+    }
+}
@@ -1,10 +1,15 @@
+mod camera;
 mod components;
+mod dungeon;
 mod game_log;
+mod game_seed;
 mod gui;
 mod map;
+mod map_builders;
 mod menu;
 mod player;
 mod random_table;
+mod raws;
 mod rect;
 mod spawner;
 mod state;
@@ -20,68 +25,113 @@ use crate::systems::particle;
 
 fn main() -> rltk::BError {
     use rltk::RltkBuilder;
+
+    // `--spawn-table N` prints what can spawn at depth N instead of starting the game
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(flag) = args.iter().position(|arg| arg == "--spawn-table") {
+        match args.get(flag + 1).and_then(|depth| depth.parse().ok()) {
+            Some(depth) => print_spawn_table(depth),
+            None => eprintln!("Usage: --spawn-table <depth>"),
+        }
+        return Ok(());
+    }
+
+    // `--mapgen-visualizer` plays back how each level was generated before it starts
+    map_builders::set_mapgen_visualizer(args.iter().any(|arg| arg == "--mapgen-visualizer"));
+
     let mut context = RltkBuilder::simple80x50()
         .with_title("Roguelike Tutorial")
         .build()?;
     context.with_post_scanlines(true);
 
-    let mut gs = State {
-        ecs: World::new(),
+    // Nothing is generated until a seed has been chosen, so the menu runs on an empty world
+    let gs = State {
+        ecs: create_world(),
+        mapgen_next_state: None,
+        mapgen_history: Vec::new(),
+        mapgen_index: 0,
+        mapgen_timer: 0.0,
     };
 
-    register_components(&mut gs);
-
-    // it should be inserted earlier than the rest, otherwise it will crash
-    gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    rltk::main_loop(context, gs)
+}
 
-    let map = map::Map::new_with_rooms_and_corridors(1);
+fn print_spawn_table(map_depth: i32) {
+    println!("Spawn table for depth {}:", map_depth);
+    for (name, chance) in raws::raws().room_table(map_depth).odds() {
+        println!("  {:<24}{:>6.1}%", name, 100.0 * chance);
+    }
+}
 
-    let (player_x, player_y) = map.rooms[0].center();
-    let player = spawner::create_player(&mut gs.ecs, player_x, player_y);
-    gs.ecs.insert(Point::new(player_x, player_y));
+/// A world with every component registered and the resources that exist before any run starts.
+/// New and loaded games each get a fresh one, so nothing from an earlier run (not even entity ids)
+/// can change how the next one plays out.
+pub fn create_world() -> World {
+    let mut ecs = World::new();
+    register_components(&mut ecs);
 
-    gs.ecs.insert(rltk::RandomNumberGenerator::new());
-    for room in map.rooms.iter().skip(1) {
-        spawner::spawn_room(&mut gs.ecs, room, &map, 1);
-    }
-    gs.ecs.insert(map);
-    gs.ecs.insert(player);
-    gs.ecs.insert(RunState::PreRun);
-    gs.ecs.insert(game_log::GameLog { entries: vec!["Welcome to Rusty Roguelike".to_string()] });
-    gs.ecs.insert(particle::ParticleBuilder::new());
+    // it should be inserted earlier than the rest, otherwise it will crash
+    ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
 
-    rltk::main_loop(context, gs)
+    ecs.insert(Point::new(0, 0));
+    ecs.insert(RunState::MainMenu { menu_selection: gui::MainMenuSelection::NewGame });
+    ecs.insert(particle::ParticleBuilder::new());
+    ecs.insert(gui::TargetingCursor::default());
+    ecs.insert(game_log::GameLog { entries: Vec::new() });
+    ecs
 }
 
-fn register_components(gs: &mut State) {
-    gs.ecs.register::<AreaOfEffect>();
-    gs.ecs.register::<BlocksTile>();
-    gs.ecs.register::<CombatStats>();
-    gs.ecs.register::<Confusion>();
-    gs.ecs.register::<Consumable>();
-    gs.ecs.register::<DefenseBonus>();
-    gs.ecs.register::<Equipped>();
-    gs.ecs.register::<Equippable>();
-    gs.ecs.register::<Item>();
-    gs.ecs.register::<InBackpack>();
-    gs.ecs.register::<InflictsDamage>();
-    gs.ecs.register::<MeleePowerBonus>();
-    gs.ecs.register::<Monster>();
-    gs.ecs.register::<Name>();
-    gs.ecs.register::<ParticleLifetime>();
-    gs.ecs.register::<Player>();
-    gs.ecs.register::<Position>();
-    gs.ecs.register::<ProvidesHealing>();
-    gs.ecs.register::<Ranged>();
-    gs.ecs.register::<Renderable>();
-    gs.ecs.register::<SufferDamage>();
-    gs.ecs.register::<Viewshed>();
-    gs.ecs.register::<WantsToDropItem>();
-    gs.ecs.register::<WantsToUseItem>();
-    gs.ecs.register::<WantsToMelee>();
-    gs.ecs.register::<WantsToPickupItem>();
-    gs.ecs.register::<WantsToRemoveItem>();
+fn register_components(ecs: &mut World) {
+    ecs.register::<Ammunition>();
+    ecs.register::<AreaOfEffect>();
+    ecs.register::<Attributes>();
+    ecs.register::<BlocksTile>();
+    ecs.register::<BlocksVisibility>();
+    ecs.register::<CausesStatus>();
+    ecs.register::<CombatStats>();
+    ecs.register::<Consumable>();
+    ecs.register::<DefenseBonus>();
+    ecs.register::<Door>();
+    ecs.register::<EntityMoved>();
+    ecs.register::<EntryTrigger>();
+    ecs.register::<Equipped>();
+    ecs.register::<Equippable>();
+    ecs.register::<Experience>();
+    ecs.register::<GrantsExperience>();
+    ecs.register::<Hidden>();
+    ecs.register::<HungerClock>();
+    ecs.register::<Item>();
+    ecs.register::<InBackpack>();
+    ecs.register::<Initiative>();
+    ecs.register::<InflictsDamage>();
+    ecs.register::<KnownToMonsters>();
+    ecs.register::<MeleeWeapon>();
+    ecs.register::<Monster>();
+    ecs.register::<MyTurn>();
+    ecs.register::<Name>();
+    ecs.register::<OtherLevelPosition>();
+    ecs.register::<ParticleLifetime>();
+    ecs.register::<Player>();
+    ecs.register::<Position>();
+    ecs.register::<ProvidesFood>();
+    ecs.register::<ProvidesHealing>();
+    ecs.register::<Ranged>();
+    ecs.register::<RangedWeapon>();
+    ecs.register::<Renderable>();
+    ecs.register::<SingleActivation>();
+    ecs.register::<Skills>();
+    ecs.register::<SpeedPenalty>();
+    ecs.register::<StatusEffects>();
+    ecs.register::<SufferDamage>();
+    ecs.register::<TeleportsVictim>();
+    ecs.register::<Viewshed>();
+    ecs.register::<WantsToDropItem>();
+    ecs.register::<WantsToUseItem>();
+    ecs.register::<WantsToMelee>();
+    ecs.register::<WantsToPickupItem>();
+    ecs.register::<WantsToRemoveItem>();
+    ecs.register::<WantsToShoot>();
 
-    gs.ecs.register::<SerializationHelper>();
-    gs.ecs.register::<SimpleMarker<SerializeMe>>();
+    ecs.register::<SerializationHelper>();
+    ecs.register::<SimpleMarker<SerializeMe>>();
 }
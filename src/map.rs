@@ -1,20 +1,20 @@
+use crate::components::{BlocksTile, BlocksVisibility, Door, Renderable};
 use crate::rect::Rect;
-use rltk::{Algorithm2D, BaseMap, Point, RandomNumberGenerator, RGB, Rltk};
+use rltk::{Algorithm2D, BaseMap, Point, RGB};
 use serde::{Serialize, Deserialize};
 use specs::prelude::*;
-use specs::World;
-use std::cmp::{max, min};
 use std::collections::HashSet;
 
-pub(crate) const MAP_WIDTH: usize = 80;
-pub(crate) const MAP_HEIGHT: usize = 43;
-pub(crate) const MAP_CELL_COUNT: usize = MAP_HEIGHT * MAP_WIDTH;
+/// The size of a level that doesn't ask for anything else; it fills the screen exactly.
+pub(crate) const MAP_WIDTH: i32 = 80;
+pub(crate) const MAP_HEIGHT: i32 = 43;
 
 #[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
     DownStairs,
+    UpStairs,
 }
 
 #[derive(Default, Serialize, Deserialize, Clone)]
@@ -29,6 +29,21 @@ pub struct Map {
     pub depth: i32,
     pub bloodstains: HashSet<usize>,
 
+    /// Tiles an entity stops you seeing through, such as a closed door. Rebuilt by the map
+    /// indexing system.
+    #[serde(skip)]
+    pub view_blocked: HashSet<usize>,
+
+    /// Tiles holding a closed door. They are blocked, but pathfinding may still go through them
+    /// since whoever gets there can open the door.
+    #[serde(skip)]
+    pub closed_doors: HashSet<usize>,
+
+    /// Tiles holding a trap the monsters know about. They path around them, unless that is where
+    /// they are trying to get to. Rebuilt by the map indexing system.
+    #[serde(skip)]
+    pub known_traps: HashSet<usize>,
+
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
     pub tile_content: Vec<Vec<Entity>>,
@@ -39,93 +54,29 @@ impl Map {
         (y * self.width + x) as usize
     }
 
-    pub fn new_with_rooms_and_corridors(depth: i32) -> Map {
-        let mut map = Map {
-            tiles: vec![TileType::Wall; MAP_CELL_COUNT],
+    pub fn new(depth: i32, width: i32, height: i32) -> Map {
+        let cell_count = (width * height) as usize;
+        Map {
+            tiles: vec![TileType::Wall; cell_count],
             rooms: Vec::new(),
-            width: MAP_WIDTH as i32,
-            height: MAP_HEIGHT as i32,
-            revealed_tiles: vec![false; MAP_CELL_COUNT],
-            visible_tiles: vec![false; MAP_CELL_COUNT],
-            blocked: vec![false; MAP_CELL_COUNT],
-            tile_content: vec![Vec::new(); MAP_CELL_COUNT],
+            width,
+            height,
+            revealed_tiles: vec![false; cell_count],
+            visible_tiles: vec![false; cell_count],
+            blocked: vec![false; cell_count],
+            tile_content: vec![Vec::new(); cell_count],
             depth,
             bloodstains: HashSet::new(),
-        };
-
-        const MAX_ROOMS: i32 = 30;
-        const MIN_SIZE: i32 = 6;
-        const MAX_SIZE: i32 = 10;
-
-        let mut rng = RandomNumberGenerator::new();
-
-        for _ in 0..MAX_ROOMS {
-            let w = rng.range(MIN_SIZE, MAX_SIZE);
-            let h = rng.range(MIN_SIZE, MAX_SIZE);
-            let x = rng.roll_dice(1, map.width - w - 1) - 1;
-            let y = rng.roll_dice(1, map.height - h - 1) - 1;
-            let new_room = Rect::new(x, y, w, h);
-            let mut ok = true;
-            for other_room in map.rooms.iter() {
-                if new_room.intersect(other_room) { ok = false }
-            }
-            if ok {
-                map.apply_room_to_map(&new_room);
-
-                if !map.rooms.is_empty() {
-                    let (new_x, new_y) = new_room.center();
-                    let (prev_x, prev_y) = map.rooms[map.rooms.len() - 1].center();
-                    if rng.range(0, 2) == 1 {
-                        map.apply_horizontal_tunnel(prev_x, new_x, prev_y);
-                        map.apply_vertical_tunnel(prev_y, new_y, new_x);
-                    } else {
-                        map.apply_vertical_tunnel(prev_y, new_y, prev_x);
-                        map.apply_horizontal_tunnel(prev_x, new_x, new_y);
-                    }
-                }
-
-                map.rooms.push(new_room);
-            }
-        }
-
-        let stairs_position = map.rooms[map.rooms.len() - 1].center();
-        let stairs_idx = map.xy_idx(stairs_position.0, stairs_position.1);
-        map.tiles[stairs_idx] = TileType::DownStairs;
-
-        map
-    }
-
-    fn apply_room_to_map(&mut self, room: &Rect) {
-        for y in room.y1 + 1..=room.y2 {
-            for x in room.x1 + 1..=room.x2 {
-                let idx = self.xy_idx(x, y);
-                self.tiles[idx] = TileType::Floor;
-            }
-        }
-    }
-
-    fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
-        for x in min(x1, x2)..=max(x1, x2) {
-            let idx = self.xy_idx(x, y);
-            if idx > 0 && idx < self.width as usize * self.height as usize {
-                self.tiles[idx as usize] = TileType::Floor;
-            }
-        }
-    }
-
-    fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32) {
-        for y in min(y1, y2)..=max(y1, y2) {
-            let idx = self.xy_idx(x, y);
-            if idx > 0 && idx < self.width as usize * self.height as usize {
-                self.tiles[idx as usize] = TileType::Floor;
-            }
+            view_blocked: HashSet::new(),
+            closed_doors: HashSet::new(),
+            known_traps: HashSet::new(),
         }
     }
 
     fn is_exit_valid(&self, x: i32, y: i32) -> bool {
         if x < 1 || x > self.width - 1 || y < 1 || y > self.height - 1 { return false; }
         let idx = self.xy_idx(x, y);
-        !self.blocked[idx]
+        (!self.blocked[idx] || self.closed_doors.contains(&idx)) && !self.known_traps.contains(&idx)
     }
 
     pub fn populate_blocked_by_terrain(&mut self) {
@@ -149,7 +100,7 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] == TileType::Wall
+        self.tiles[idx] == TileType::Wall || self.view_blocked.contains(&idx)
     }
 
     fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
@@ -181,48 +132,73 @@ impl BaseMap for Map {
     }
 }
 
-pub fn draw_map(ecs: &World, ctx: &mut Rltk) {
-    let map = ecs.fetch::<Map>();
-
-    let mut y = 0;
-    let mut x = 0;
-    for (idx, tile) in map.tiles.iter().enumerate() {
-        if map.revealed_tiles[idx] {
-            let glyph;
-            let mut fg;
-            let mut bg = RGB::from_f32(0., 0., 0.);
-            match tile {
-                TileType::Floor => {
-                    glyph = rltk::to_cp437('.');
-                    fg = RGB::from_f32(0.0, 0.5, 0.5);
-                }
-                TileType::Wall => {
-                    glyph = wall_glyph(&*map, x, y);
-                    fg = RGB::from_f32(0., 1.0, 0.);
-                }
-                TileType::DownStairs => {
-                    glyph = rltk::to_cp437('>');
-                    fg = RGB::from_f32(0., 1.0, 1.0);
-                }
-            }
-            if map.bloodstains.contains(&idx) { bg = RGB::from_f32(0.75, 0., 0.); }
-            if !map.visible_tiles[idx] {
-                fg = fg.to_greyscale();
-                bg = RGB::from_f32(0., 0., 0.); // Don't show stains out of visual range
+/// Opens any closed door on `idx`, so it stops blocking movement and sight. Returns whether there
+/// was a door to open; whoever opened it has spent their move doing so.
+pub fn open_door(
+    map: &mut Map,
+    idx: usize,
+    doors: &mut WriteStorage<Door>,
+    blocks_movement: &mut WriteStorage<BlocksTile>,
+    blocks_visibility: &mut WriteStorage<BlocksVisibility>,
+    renderables: &mut WriteStorage<Renderable>,
+) -> bool {
+    let mut opened = false;
+    for entity in map.tile_content[idx].iter() {
+        if let Some(door) = doors.get_mut(*entity) {
+            if door.open { continue; }
+            door.open = true;
+            blocks_movement.remove(*entity);
+            blocks_visibility.remove(*entity);
+            if let Some(renderable) = renderables.get_mut(*entity) {
+                renderable.glyph = rltk::to_cp437('/');
             }
-            ctx.set(x, y, fg, bg, glyph);
+            opened = true;
         }
+    }
+
+    if opened {
+        map.blocked[idx] = false;
+        map.view_blocked.remove(&idx);
+        map.closed_doors.remove(&idx);
+    }
+    opened
+}
 
-        x += 1;
-        if x >= MAP_WIDTH as i32 {
-            x = 0;
-            y += 1;
+/// The glyph and colours to draw a revealed tile with.
+pub fn tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
+    let glyph;
+    let mut fg;
+    let mut bg = RGB::from_f32(0., 0., 0.);
+    match map.tiles[idx] {
+        TileType::Floor => {
+            glyph = rltk::to_cp437('.');
+            fg = RGB::from_f32(0.0, 0.5, 0.5);
         }
+        TileType::Wall => {
+            let x = idx as i32 % map.width;
+            let y = idx as i32 / map.width;
+            glyph = wall_glyph(map, x, y);
+            fg = RGB::from_f32(0., 1.0, 0.);
+        }
+        TileType::DownStairs => {
+            glyph = rltk::to_cp437('>');
+            fg = RGB::from_f32(0., 1.0, 1.0);
+        }
+        TileType::UpStairs => {
+            glyph = rltk::to_cp437('<');
+            fg = RGB::from_f32(0., 1.0, 1.0);
+        }
+    }
+    if map.bloodstains.contains(&idx) { bg = RGB::from_f32(0.75, 0., 0.); }
+    if !map.visible_tiles[idx] {
+        fg = fg.to_greyscale();
+        bg = RGB::from_f32(0., 0., 0.); // Don't show stains out of visual range
     }
+    (glyph, fg, bg)
 }
 
 fn wall_glyph(map: &Map, x: i32, y: i32) -> rltk::FontCharType {
-    if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 as i32 { return 35; }
+    if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 { return 35; }
     let mut mask: u8 = 0;
 
     if is_revealed_and_wall(map, x, y - 1) { mask += 1; }
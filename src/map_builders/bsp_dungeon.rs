@@ -0,0 +1,183 @@
+use rltk::RandomNumberGenerator;
+use crate::components::Position;
+use crate::map::TileType;
+use crate::map_builders::common::{apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel};
+use crate::map_builders::{BuilderMap, MapBuilder};
+use crate::rect::Rect;
+use crate::spawner;
+
+/// Leaves are never split below this size, so every leaf has room for a room plus its walls.
+const MIN_LEAF_SIZE: i32 = 8;
+const MIN_ROOM_SIZE: i32 = 4;
+
+/// One region of the subdivision. Internal nodes own two children, leaves own a room.
+struct BspNode {
+    region: Rect,
+    children: Option<(Box<BspNode>, Box<BspNode>)>,
+    room: Option<Rect>,
+}
+
+impl BspNode {
+    fn new(region: Rect) -> BspNode {
+        BspNode { region, children: None, room: None }
+    }
+
+    fn split(&mut self, rng: &mut RandomNumberGenerator) {
+        let width = self.region.x2 - self.region.x1;
+        let height = self.region.y2 - self.region.y1;
+        let can_split_x = width >= MIN_LEAF_SIZE * 2;
+        let can_split_y = height >= MIN_LEAF_SIZE * 2;
+
+        // Prefer cutting across the long side so regions stay roughly square
+        let split_vertically = match (can_split_x, can_split_y) {
+            (false, false) => return,
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) => {
+                if width * 4 > height * 5 { true } else if height * 4 > width * 5 { false } else { rng.range(0, 2) == 1 }
+            }
+        };
+
+        let (first, second) = if split_vertically {
+            let split_x = rng.range(self.region.x1 + MIN_LEAF_SIZE, self.region.x2 - MIN_LEAF_SIZE + 1);
+            (
+                Rect { x1: self.region.x1, x2: split_x, y1: self.region.y1, y2: self.region.y2 },
+                Rect { x1: split_x, x2: self.region.x2, y1: self.region.y1, y2: self.region.y2 },
+            )
+        } else {
+            let split_y = rng.range(self.region.y1 + MIN_LEAF_SIZE, self.region.y2 - MIN_LEAF_SIZE + 1);
+            (
+                Rect { x1: self.region.x1, x2: self.region.x2, y1: self.region.y1, y2: split_y },
+                Rect { x1: self.region.x1, x2: self.region.x2, y1: split_y, y2: self.region.y2 },
+            )
+        };
+
+        let mut first = Box::new(BspNode::new(first));
+        let mut second = Box::new(BspNode::new(second));
+        first.split(rng);
+        second.split(rng);
+        self.children = Some((first, second));
+    }
+
+    /// Places a room in every leaf. The room is kept one tile away from the leaf edges, so
+    /// neighbouring leaves always have a wall between them.
+    fn create_rooms(&mut self, rng: &mut RandomNumberGenerator) {
+        match &mut self.children {
+            Some((first, second)) => {
+                first.create_rooms(rng);
+                second.create_rooms(rng);
+            }
+            None => {
+                let max_w = self.region.x2 - self.region.x1 - 1;
+                let max_h = self.region.y2 - self.region.y1 - 1;
+                let w = rng.range(MIN_ROOM_SIZE, max_w + 1);
+                let h = rng.range(MIN_ROOM_SIZE, max_h + 1);
+                let x = rng.range(self.region.x1, self.region.x2 - w);
+                let y = rng.range(self.region.y1, self.region.y2 - h);
+                self.room = Some(Rect::new(x, y, w, h));
+            }
+        }
+    }
+
+    fn collect_rooms(&self, rooms: &mut Vec<Rect>) {
+        if let Some(room) = self.room {
+            rooms.push(room);
+        }
+        if let Some((first, second)) = &self.children {
+            first.collect_rooms(rooms);
+            second.collect_rooms(rooms);
+        }
+    }
+
+    /// Joins the two halves of every internal node, bottom-up. Because each subtree is already
+    /// connected by the time its parent is processed, one corridor per node is enough.
+    fn connect(&self, data: &mut BuilderMap, rng: &mut RandomNumberGenerator) {
+        if let Some((first, second)) = &self.children {
+            first.connect(data, rng);
+            second.connect(data, rng);
+
+            let mut first_rooms = Vec::new();
+            let mut second_rooms = Vec::new();
+            first.collect_rooms(&mut first_rooms);
+            second.collect_rooms(&mut second_rooms);
+
+            // Use the closest pair of rooms across the split to keep corridors short
+            let mut best: Option<(Rect, Rect, i32)> = None;
+            for a in first_rooms.iter() {
+                for b in second_rooms.iter() {
+                    let (ax, ay) = a.center();
+                    let (bx, by) = b.center();
+                    let distance = i32::abs(ax - bx) + i32::abs(ay - by);
+                    if best.is_none_or(|(_, _, d)| distance < d) {
+                        best = Some((*a, *b, distance));
+                    }
+                }
+            }
+
+            if let Some((a, b, _)) = best {
+                let (ax, ay) = a.center();
+                let (bx, by) = b.center();
+                if rng.range(0, 2) == 1 {
+                    apply_horizontal_tunnel(&mut data.map, ax, bx, ay);
+                    apply_vertical_tunnel(&mut data.map, ay, by, bx);
+                } else {
+                    apply_vertical_tunnel(&mut data.map, ay, by, ax);
+                    apply_horizontal_tunnel(&mut data.map, ax, bx, by);
+                }
+                data.take_snapshot();
+            }
+        }
+    }
+}
+
+/// Recursively subdivides the whole map and puts one room in each leaf, which spreads rooms
+/// evenly instead of leaving the gaps random placement does.
+pub struct BspDungeonBuilder {
+    data: BuilderMap,
+}
+
+impl BspDungeonBuilder {
+    pub fn new(depth: i32, width: i32, height: i32) -> BspDungeonBuilder {
+        BspDungeonBuilder {
+            data: BuilderMap::new(depth, width, height),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        let mut root = BspNode::new(Rect { x1: 0, x2: self.data.map.width - 1, y1: 0, y2: self.data.map.height - 1 });
+        root.split(rng);
+        root.create_rooms(rng);
+
+        let mut rooms = Vec::new();
+        root.collect_rooms(&mut rooms);
+        for room in rooms.iter() {
+            apply_room_to_map(&mut self.data.map, room);
+            self.data.take_snapshot();
+        }
+        root.connect(&mut self.data, rng);
+        self.data.map.rooms = rooms;
+
+        let stairs_position = self.data.map.rooms[self.data.map.rooms.len() - 1].center();
+        let stairs_idx = self.data.map.xy_idx(stairs_position.0, stairs_position.1);
+        self.data.map.tiles[stairs_idx] = TileType::DownStairs;
+        self.data.take_snapshot();
+
+        let (start_x, start_y) = self.data.map.rooms[0].center();
+        self.data.starting_position = Position { x: start_x, y: start_y };
+
+        // Spawn bad guys everywhere except the starting room
+        for room in self.data.map.rooms.iter().skip(1) {
+            spawner::spawn_room(&self.data.map, rng, room, self.data.depth, &mut self.data.spawn_list);
+        }
+    }
+}
+
+impl MapBuilder for BspDungeonBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn build_data(&self) -> &BuilderMap {
+        &self.data
+    }
+}
@@ -0,0 +1,112 @@
+use rltk::RandomNumberGenerator;
+use crate::components::Position;
+use crate::map::TileType;
+use crate::map_builders::common::remove_unreachable_areas_returning_most_distant;
+use crate::map_builders::{BuilderMap, MapBuilder};
+use crate::spawner;
+
+const SMOOTHING_ITERATIONS: i32 = 15;
+
+/// Natural-looking caves: random noise smoothed by a "wall if crowded or isolated" rule.
+pub struct CellularAutomataBuilder {
+    data: BuilderMap,
+}
+
+impl CellularAutomataBuilder {
+    pub fn new(depth: i32, width: i32, height: i32) -> CellularAutomataBuilder {
+        CellularAutomataBuilder {
+            data: BuilderMap::new(depth, width, height),
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        // Seed the map with roughly 55% floor
+        for y in 1..self.data.map.height - 1 {
+            for x in 1..self.data.map.width - 1 {
+                let idx = self.data.map.xy_idx(x, y);
+                self.data.map.tiles[idx] = if rng.roll_dice(1, 100) > 55 { TileType::Wall } else { TileType::Floor };
+            }
+        }
+        self.data.take_snapshot();
+
+        for _ in 0..SMOOTHING_ITERATIONS {
+            self.smooth();
+            self.data.take_snapshot();
+        }
+        self.fill_nubs();
+        self.data.take_snapshot();
+
+        // Start on the floor tile closest to the middle
+        let start_idx = self.closest_floor_to_centre();
+        self.data.starting_position = Position { x: start_idx as i32 % self.data.map.width, y: start_idx as i32 / self.data.map.width };
+
+        let exit_tile = remove_unreachable_areas_returning_most_distant(&mut self.data.map, start_idx);
+        self.data.map.tiles[exit_tile] = TileType::DownStairs;
+        self.data.take_snapshot();
+
+        spawner::spawn_voronoi_regions(&self.data.map, rng, start_idx, self.data.depth, &mut self.data.spawn_list);
+    }
+
+    /// Searches the whole map, since the middle row can be solid wall all the way to the edge.
+    fn closest_floor_to_centre(&self) -> usize {
+        let centre = rltk::Point::new(self.data.map.width / 2, self.data.map.height / 2);
+        let width = self.data.map.width;
+        self.data.map.tiles.iter().enumerate()
+            .filter(|(_, tile)| **tile == TileType::Floor)
+            .map(|(idx, _)| idx)
+            .min_by_key(|idx| {
+                let distance = rltk::DistanceAlg::PythagorasSquared.distance2d(centre, rltk::Point::new(*idx as i32 % width, *idx as i32 / width));
+                distance as i32
+            })
+            .expect("Cave has no floor to start on")
+    }
+
+    fn smooth(&mut self) {
+        let mut new_tiles = self.data.map.tiles.clone();
+
+        for y in 1..self.data.map.height - 1 {
+            for x in 1..self.data.map.width - 1 {
+                let idx = self.data.map.xy_idx(x, y);
+                let mut neighbors = 0;
+                for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                    if self.data.map.tiles[self.data.map.xy_idx(x + dx, y + dy)] == TileType::Wall { neighbors += 1; }
+                }
+
+                new_tiles[idx] = if neighbors > 4 || neighbors == 0 { TileType::Wall } else { TileType::Floor };
+            }
+        }
+
+        self.data.map.tiles = new_tiles;
+    }
+
+    /// The smoothing rule never settles next to the outer wall and leaves a checkerboard of
+    /// single floor tiles there. Those all have three walls around them, so fill them in.
+    fn fill_nubs(&mut self) {
+        let mut new_tiles = self.data.map.tiles.clone();
+
+        for y in 1..self.data.map.height - 1 {
+            for x in 1..self.data.map.width - 1 {
+                let idx = self.data.map.xy_idx(x, y);
+                if self.data.map.tiles[idx] != TileType::Floor { continue; }
+
+                let mut walls = 0;
+                for (dx, dy) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+                    if self.data.map.tiles[self.data.map.xy_idx(x + dx, y + dy)] == TileType::Wall { walls += 1; }
+                }
+                if walls >= 3 { new_tiles[idx] = TileType::Wall; }
+            }
+        }
+
+        self.data.map.tiles = new_tiles;
+    }
+}
+
+impl MapBuilder for CellularAutomataBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn build_data(&self) -> &BuilderMap {
+        &self.data
+    }
+}
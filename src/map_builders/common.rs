@@ -0,0 +1,76 @@
+use crate::map::{Map, TileType};
+use crate::map_builders::show_mapgen_visualizer;
+use crate::rect::Rect;
+use std::cmp::{max, min};
+
+/// Records a fully revealed copy of `map` for the generation visualizer. Does nothing unless the
+/// visualizer is switched on, so normal play doesn't pay for the clones.
+pub fn snapshot_map(history: &mut Vec<Map>, map: &Map) {
+    if show_mapgen_visualizer() {
+        let mut snapshot = map.clone();
+        for tile in snapshot.revealed_tiles.iter_mut() { *tile = true; }
+        for tile in snapshot.visible_tiles.iter_mut() { *tile = true; }
+        history.push(snapshot);
+    }
+}
+
+pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
+    for y in room.y1 + 1..=room.y2 {
+        for x in room.x1 + 1..=room.x2 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+pub fn apply_horizontal_tunnel(map: &mut Map, x1: i32, x2: i32, y: i32) {
+    for x in min(x1, x2)..=max(x1, x2) {
+        let idx = map.xy_idx(x, y);
+        if idx > 0 && idx < map.width as usize * map.height as usize {
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+pub fn apply_vertical_tunnel(map: &mut Map, y1: i32, y2: i32, x: i32) {
+    for y in min(y1, y2)..=max(y1, y2) {
+        let idx = map.xy_idx(x, y);
+        if idx > 0 && idx < map.width as usize * map.height as usize {
+            map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+/// Walls off everything the player cannot reach from `start_idx`, and returns the reachable
+/// tile that is furthest away from it.
+pub fn remove_unreachable_areas_returning_most_distant(map: &mut Map, start_idx: usize) -> usize {
+    map.populate_blocked_by_terrain();
+    let map_starts: Vec<usize> = vec![start_idx];
+    let max_depth = (map.width * map.height) as f32;
+    let dijkstra_map = rltk::DijkstraMap::new(map.width as usize, map.height as usize, &map_starts, &*map, max_depth);
+
+    let mut exit_tile = (0, 0.0f32);
+    for (i, tile) in map.tiles.iter_mut().enumerate() {
+        if *tile == TileType::Floor {
+            let distance_to_start = dijkstra_map.map[i];
+            if distance_to_start == f32::MAX {
+                *tile = TileType::Wall;
+            } else if distance_to_start > exit_tile.1 {
+                exit_tile.0 = i;
+                exit_tile.1 = distance_to_start;
+            }
+        }
+    }
+
+    exit_tile.0
+}
+
+/// True when every walkable tile can still be reached from `start_idx`.
+pub fn is_fully_connected(map: &mut Map, start_idx: usize) -> bool {
+    map.populate_blocked_by_terrain();
+    let map_starts: Vec<usize> = vec![start_idx];
+    let max_depth = (map.width * map.height) as f32;
+    let dijkstra_map = rltk::DijkstraMap::new(map.width as usize, map.height as usize, &map_starts, &*map, max_depth);
+
+    map.tiles.iter().enumerate().all(|(i, tile)| *tile == TileType::Wall || dijkstra_map.map[i] < f32::MAX)
+}
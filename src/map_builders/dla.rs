@@ -0,0 +1,164 @@
+use rltk::{Point, RandomNumberGenerator};
+use crate::components::Position;
+use crate::map::TileType;
+use crate::map_builders::common::remove_unreachable_areas_returning_most_distant;
+use crate::map_builders::{BuilderMap, MapBuilder};
+use crate::spawner;
+
+/// A particle only paints a brushful of floor, so recording every one would make for a very
+/// long replay.
+const PARTICLES_PER_SNAPSHOT: i32 = 10;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum DLAAlgorithm {
+    WalkInwards,
+    WalkOutwards,
+    CentralAttractor,
+}
+
+/// Diffusion-limited aggregation: particles wander until they touch the existing cave and stick,
+/// growing a coral-like structure out of the starting point.
+pub struct DLABuilder {
+    data: BuilderMap,
+    algorithm: DLAAlgorithm,
+    brush_size: i32,
+    floor_percent: f32,
+}
+
+impl DLABuilder {
+    pub fn new(depth: i32, width: i32, height: i32, algorithm: DLAAlgorithm, brush_size: i32, floor_percent: f32) -> DLABuilder {
+        DLABuilder {
+            data: BuilderMap::new(depth, width, height),
+            algorithm,
+            brush_size,
+            floor_percent,
+        }
+    }
+
+    /// Particles start anywhere and drift until they hit the cave, growing it inwards.
+    pub fn walk_inwards(depth: i32, width: i32, height: i32) -> DLABuilder {
+        DLABuilder::new(depth, width, height, DLAAlgorithm::WalkInwards, 1, 0.25)
+    }
+
+    /// Particles leave the centre and dig where they first hit rock, growing it outwards.
+    pub fn walk_outwards(depth: i32, width: i32, height: i32) -> DLABuilder {
+        DLABuilder::new(depth, width, height, DLAAlgorithm::WalkOutwards, 2, 0.25)
+    }
+
+    /// Particles fly straight at the centre, which gives long radial spokes.
+    pub fn central_attractor(depth: i32, width: i32, height: i32) -> DLABuilder {
+        DLABuilder::new(depth, width, height, DLAAlgorithm::CentralAttractor, 2, 0.25)
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        // Carve a small cross in the middle for the particles to stick to
+        self.data.starting_position = Position { x: self.data.map.width / 2, y: self.data.map.height / 2 };
+        let start_idx = self.data.map.xy_idx(self.data.starting_position.x, self.data.starting_position.y);
+        self.data.map.tiles[start_idx] = TileType::Floor;
+        self.data.map.tiles[start_idx - 1] = TileType::Floor;
+        self.data.map.tiles[start_idx + 1] = TileType::Floor;
+        self.data.map.tiles[start_idx - self.data.map.width as usize] = TileType::Floor;
+        self.data.map.tiles[start_idx + self.data.map.width as usize] = TileType::Floor;
+
+        let total_tiles = self.data.map.width * self.data.map.height;
+        let desired_floor_tiles = (self.floor_percent * total_tiles as f32) as usize;
+        let mut floor_tile_count = self.floor_tile_count();
+        let mut particle_count = 0;
+
+        while floor_tile_count < desired_floor_tiles {
+            match self.algorithm {
+                DLAAlgorithm::WalkInwards => {
+                    let mut digger_x = rng.roll_dice(1, self.data.map.width - 3) + 1;
+                    let mut digger_y = rng.roll_dice(1, self.data.map.height - 3) + 1;
+                    let mut prev_x = digger_x;
+                    let mut prev_y = digger_y;
+                    let mut digger_idx = self.data.map.xy_idx(digger_x, digger_y);
+                    while self.data.map.tiles[digger_idx] == TileType::Wall {
+                        prev_x = digger_x;
+                        prev_y = digger_y;
+                        self.stagger(rng, &mut digger_x, &mut digger_y);
+                        digger_idx = self.data.map.xy_idx(digger_x, digger_y);
+                    }
+                    self.paint(prev_x, prev_y);
+                }
+                DLAAlgorithm::WalkOutwards => {
+                    let mut digger_x = self.data.starting_position.x;
+                    let mut digger_y = self.data.starting_position.y;
+                    let mut digger_idx = self.data.map.xy_idx(digger_x, digger_y);
+                    while self.data.map.tiles[digger_idx] == TileType::Floor {
+                        self.stagger(rng, &mut digger_x, &mut digger_y);
+                        digger_idx = self.data.map.xy_idx(digger_x, digger_y);
+                    }
+                    self.paint(digger_x, digger_y);
+                }
+                DLAAlgorithm::CentralAttractor => {
+                    let mut digger_x = rng.roll_dice(1, self.data.map.width - 3) + 1;
+                    let mut digger_y = rng.roll_dice(1, self.data.map.height - 3) + 1;
+                    let mut prev_x = digger_x;
+                    let mut prev_y = digger_y;
+                    let mut digger_idx = self.data.map.xy_idx(digger_x, digger_y);
+
+                    let mut path = rltk::line2d(
+                        rltk::LineAlg::Bresenham,
+                        Point::new(digger_x, digger_y),
+                        Point::new(self.data.starting_position.x, self.data.starting_position.y),
+                    ).into_iter();
+                    while self.data.map.tiles[digger_idx] == TileType::Wall {
+                        let Some(step) = path.next() else { break; };
+                        prev_x = digger_x;
+                        prev_y = digger_y;
+                        digger_x = step.x;
+                        digger_y = step.y;
+                        digger_idx = self.data.map.xy_idx(digger_x, digger_y);
+                    }
+                    self.paint(prev_x, prev_y);
+                }
+            }
+
+            floor_tile_count = self.floor_tile_count();
+            particle_count += 1;
+            if particle_count % PARTICLES_PER_SNAPSHOT == 0 { self.data.take_snapshot(); }
+        }
+
+        let exit_tile = remove_unreachable_areas_returning_most_distant(&mut self.data.map, start_idx);
+        self.data.map.tiles[exit_tile] = TileType::DownStairs;
+        self.data.take_snapshot();
+
+        spawner::spawn_voronoi_regions(&self.data.map, rng, start_idx, self.data.depth, &mut self.data.spawn_list);
+    }
+
+    fn stagger(&self, rng: &mut RandomNumberGenerator, x: &mut i32, y: &mut i32) {
+        match rng.roll_dice(1, 4) {
+            1 => { if *x > 2 { *x -= 1; } }
+            2 => { if *x < self.data.map.width - 2 { *x += 1; } }
+            3 => { if *y > 2 { *y -= 1; } }
+            _ => { if *y < self.data.map.height - 2 { *y += 1; } }
+        }
+    }
+
+    fn paint(&mut self, x: i32, y: i32) {
+        let half_brush = self.brush_size / 2;
+        for brush_y in y - half_brush..=y + half_brush {
+            for brush_x in x - half_brush..=x + half_brush {
+                if brush_x > 1 && brush_x < self.data.map.width - 1 && brush_y > 1 && brush_y < self.data.map.height - 1 {
+                    let idx = self.data.map.xy_idx(brush_x, brush_y);
+                    self.data.map.tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+    }
+
+    fn floor_tile_count(&self) -> usize {
+        self.data.map.tiles.iter().filter(|tile| **tile == TileType::Floor).count()
+    }
+}
+
+impl MapBuilder for DLABuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn build_data(&self) -> &BuilderMap {
+        &self.data
+    }
+}
@@ -0,0 +1,117 @@
+use rltk::RandomNumberGenerator;
+use crate::components::Position;
+use crate::map::TileType;
+use crate::map_builders::common::remove_unreachable_areas_returning_most_distant;
+use crate::map_builders::{BuilderMap, MapBuilder};
+use crate::spawner;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum DrunkSpawnMode {
+    StartingPoint,
+    Random,
+}
+
+pub struct DrunkardSettings {
+    pub spawn_mode: DrunkSpawnMode,
+    pub drunken_lifetime: i32,
+    pub floor_percent: f32,
+}
+
+/// Sends diggers stumbling around the map until enough of it has been carved out.
+pub struct DrunkardsWalkBuilder {
+    data: BuilderMap,
+    settings: DrunkardSettings,
+}
+
+impl DrunkardsWalkBuilder {
+    pub fn new(depth: i32, width: i32, height: i32, settings: DrunkardSettings) -> DrunkardsWalkBuilder {
+        DrunkardsWalkBuilder {
+            data: BuilderMap::new(depth, width, height),
+            settings,
+        }
+    }
+
+    /// Every digger sets off from the middle and wanders a long way, giving one big open cave.
+    pub fn open_area(depth: i32, width: i32, height: i32) -> DrunkardsWalkBuilder {
+        DrunkardsWalkBuilder::new(depth, width, height, DrunkardSettings {
+            spawn_mode: DrunkSpawnMode::StartingPoint,
+            drunken_lifetime: 400,
+            floor_percent: 0.5,
+        })
+    }
+
+    /// Diggers start anywhere and wander a long way, joining up into wide halls.
+    pub fn open_halls(depth: i32, width: i32, height: i32) -> DrunkardsWalkBuilder {
+        DrunkardsWalkBuilder::new(depth, width, height, DrunkardSettings {
+            spawn_mode: DrunkSpawnMode::Random,
+            drunken_lifetime: 400,
+            floor_percent: 0.5,
+        })
+    }
+
+    /// Lots of short-lived diggers with a lower target, which leaves narrow twisting passages.
+    pub fn winding_passages(depth: i32, width: i32, height: i32) -> DrunkardsWalkBuilder {
+        DrunkardsWalkBuilder::new(depth, width, height, DrunkardSettings {
+            spawn_mode: DrunkSpawnMode::Random,
+            drunken_lifetime: 100,
+            floor_percent: 0.4,
+        })
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        self.data.starting_position = Position { x: self.data.map.width / 2, y: self.data.map.height / 2 };
+        let start_idx = self.data.map.xy_idx(self.data.starting_position.x, self.data.starting_position.y);
+        self.data.map.tiles[start_idx] = TileType::Floor;
+
+        let total_tiles = self.data.map.width * self.data.map.height;
+        let desired_floor_tiles = (self.settings.floor_percent * total_tiles as f32) as usize;
+        let mut floor_tile_count = self.floor_tile_count();
+        let mut digger_count = 0;
+
+        while floor_tile_count < desired_floor_tiles {
+            let (mut drunk_x, mut drunk_y) = match self.settings.spawn_mode {
+                DrunkSpawnMode::Random if digger_count > 0 => (
+                    rng.roll_dice(1, self.data.map.width - 3) + 1,
+                    rng.roll_dice(1, self.data.map.height - 3) + 1,
+                ),
+                _ => (self.data.starting_position.x, self.data.starting_position.y),
+            };
+
+            for _ in 0..self.settings.drunken_lifetime {
+                let drunk_idx = self.data.map.xy_idx(drunk_x, drunk_y);
+                self.data.map.tiles[drunk_idx] = TileType::Floor;
+
+                match rng.roll_dice(1, 4) {
+                    1 => { if drunk_x > 2 { drunk_x -= 1; } }
+                    2 => { if drunk_x < self.data.map.width - 2 { drunk_x += 1; } }
+                    3 => { if drunk_y > 2 { drunk_y -= 1; } }
+                    _ => { if drunk_y < self.data.map.height - 2 { drunk_y += 1; } }
+                }
+            }
+
+            digger_count += 1;
+            floor_tile_count = self.floor_tile_count();
+            self.data.take_snapshot();
+        }
+
+        let exit_tile = remove_unreachable_areas_returning_most_distant(&mut self.data.map, start_idx);
+        self.data.map.tiles[exit_tile] = TileType::DownStairs;
+        self.data.take_snapshot();
+
+        spawner::spawn_voronoi_regions(&self.data.map, rng, start_idx, self.data.depth, &mut self.data.spawn_list);
+    }
+
+    fn floor_tile_count(&self) -> usize {
+        self.data.map.tiles.iter().filter(|tile| **tile == TileType::Floor).count()
+    }
+}
+
+impl MapBuilder for DrunkardsWalkBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn build_data(&self) -> &BuilderMap {
+        &self.data
+    }
+}
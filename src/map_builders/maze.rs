@@ -0,0 +1,152 @@
+use rltk::RandomNumberGenerator;
+use crate::components::Position;
+use crate::map::TileType;
+use crate::map_builders::common::remove_unreachable_areas_returning_most_distant;
+use crate::map_builders::{BuilderMap, MapBuilder};
+use crate::spawner;
+
+const CELLS_PER_SNAPSHOT: i32 = 10;
+
+/// A perfect maze carved by a recursive backtracker. Each maze cell is `cell_size` tiles wide
+/// and neighbouring cells are separated by a single wall tile.
+pub struct MazeBuilder {
+    data: BuilderMap,
+    cell_size: i32,
+}
+
+impl MazeBuilder {
+    pub fn new(depth: i32, width: i32, height: i32, cell_size: i32) -> MazeBuilder {
+        MazeBuilder {
+            data: BuilderMap::new(depth, width, height),
+            cell_size,
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        let stride = self.cell_size + 1;
+        let cells_x = (self.data.map.width - 1) / stride;
+        let cells_y = (self.data.map.height - 1) / stride;
+        let mut visited = vec![false; (cells_x * cells_y) as usize];
+
+        let mut stack: Vec<(i32, i32)> = vec![(0, 0)];
+        visited[0] = true;
+        self.carve_cell(0, 0);
+        let mut carved_count = 1;
+
+        while let Some(&(cx, cy)) = stack.last() {
+            let mut neighbors: Vec<(i32, i32)> = Vec::new();
+            for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                let (nx, ny) = (cx + dx, cy + dy);
+                if nx >= 0 && nx < cells_x && ny >= 0 && ny < cells_y && !visited[(ny * cells_x + nx) as usize] {
+                    neighbors.push((nx, ny));
+                }
+            }
+
+            if neighbors.is_empty() {
+                stack.pop();
+            } else {
+                let (nx, ny) = neighbors[(rng.roll_dice(1, neighbors.len() as i32) - 1) as usize];
+                visited[(ny * cells_x + nx) as usize] = true;
+                self.carve_cell(nx, ny);
+                self.carve_passage(cx, cy, nx, ny);
+                stack.push((nx, ny));
+
+                carved_count += 1;
+                if carved_count % CELLS_PER_SNAPSHOT == 0 { self.data.take_snapshot(); }
+            }
+        }
+
+        self.data.starting_position = Position { x: 1, y: 1 };
+        let start_idx = self.data.map.xy_idx(self.data.starting_position.x, self.data.starting_position.y);
+
+        // The maze is perfect, so nothing gets culled; this just finds the dead end furthest away
+        let exit_tile = remove_unreachable_areas_returning_most_distant(&mut self.data.map, start_idx);
+        self.data.map.tiles[exit_tile] = TileType::DownStairs;
+        self.data.take_snapshot();
+
+        spawner::spawn_voronoi_regions(&self.data.map, rng, start_idx, self.data.depth, &mut self.data.spawn_list);
+    }
+
+    fn cell_origin(&self, cx: i32, cy: i32) -> (i32, i32) {
+        let stride = self.cell_size + 1;
+        (1 + cx * stride, 1 + cy * stride)
+    }
+
+    fn carve_cell(&mut self, cx: i32, cy: i32) {
+        let (x, y) = self.cell_origin(cx, cy);
+        for ty in y..y + self.cell_size {
+            for tx in x..x + self.cell_size {
+                let idx = self.data.map.xy_idx(tx, ty);
+                self.data.map.tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+
+    /// Knocks out the wall between two adjacent cells.
+    fn carve_passage(&mut self, cx: i32, cy: i32, nx: i32, ny: i32) {
+        let (x, y) = self.cell_origin(i32::min(cx, nx), i32::min(cy, ny));
+        for i in 0..self.cell_size {
+            let (tx, ty) = if cx != nx { (x + self.cell_size, y + i) } else { (x + i, y + self.cell_size) };
+            let idx = self.data.map.xy_idx(tx, ty);
+            self.data.map.tiles[idx] = TileType::Floor;
+        }
+    }
+}
+
+impl MapBuilder for MazeBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn build_data(&self) -> &BuilderMap {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Map, MAP_HEIGHT, MAP_WIDTH};
+
+    fn build_maze(seed: u64, cell_size: i32) -> Map {
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        let mut builder = MazeBuilder::new(5, MAP_WIDTH, MAP_HEIGHT, cell_size);
+        builder.build_map(&mut rng);
+        builder.get_map()
+    }
+
+    fn distances_from_start(map: &mut Map) -> Vec<f32> {
+        map.populate_blocked_by_terrain();
+        let start_idx = map.xy_idx(1, 1);
+        let max_depth = (map.width * map.height) as f32;
+        rltk::DijkstraMap::new(map.width as usize, map.height as usize, &[start_idx], &*map, max_depth).map
+    }
+
+    #[test]
+    fn stairs_are_reachable_from_the_start() {
+        for cell_size in [1, 2] {
+            for seed in 0..10 {
+                let mut map = build_maze(seed, cell_size);
+                let stairs_idx = map.tiles.iter().position(|tile| *tile == TileType::DownStairs).expect("Maze has no stairs");
+                let distances = distances_from_start(&mut map);
+                assert!(distances[stairs_idx] < f32::MAX, "seed {} cell size {}: stairs cut off", seed, cell_size);
+            }
+        }
+    }
+
+    #[test]
+    fn every_cell_is_carved_and_connected() {
+        for cell_size in [1, 2] {
+            let mut map = build_maze(42, cell_size);
+            let stride = cell_size + 1;
+            let distances = distances_from_start(&mut map);
+            for cy in 0..(map.height - 1) / stride {
+                for cx in 0..(map.width - 1) / stride {
+                    let idx = map.xy_idx(1 + cx * stride, 1 + cy * stride);
+                    assert!(map.tiles[idx] != TileType::Wall);
+                    assert!(distances[idx] < f32::MAX);
+                }
+            }
+        }
+    }
+}
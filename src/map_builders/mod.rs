@@ -0,0 +1,124 @@
+pub mod bsp_dungeon;
+pub mod cellular_automata;
+pub mod common;
+pub mod dla;
+pub mod drunkard;
+pub mod maze;
+pub mod prefab_builder;
+pub mod simple_map;
+
+use specs::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use rltk::RandomNumberGenerator;
+use crate::components::Position;
+use crate::map::{Map, MAP_HEIGHT, MAP_WIDTH};
+use crate::map_builders::bsp_dungeon::BspDungeonBuilder;
+use crate::map_builders::common::snapshot_map;
+use crate::map_builders::cellular_automata::CellularAutomataBuilder;
+use crate::map_builders::dla::DLABuilder;
+use crate::map_builders::drunkard::DrunkardsWalkBuilder;
+use crate::map_builders::maze::MazeBuilder;
+use crate::map_builders::prefab_builder::PrefabBuilder;
+use crate::map_builders::simple_map::SimpleMapBuilder;
+use crate::spawner;
+
+const LABYRINTH_DEPTH_INTERVAL: i32 = 5;
+
+/// Open caves are allowed to sprawl well past the edges of the screen.
+const CAVERN_WIDTH: i32 = 120;
+const CAVERN_HEIGHT: i32 = 70;
+
+/// Debug switch, set by the `--mapgen-visualizer` flag: when on, builders record every generation
+/// step and the game plays them back before each level starts.
+static SHOW_MAPGEN_VISUALIZER: AtomicBool = AtomicBool::new(false);
+
+pub fn set_mapgen_visualizer(enabled: bool) {
+    SHOW_MAPGEN_VISUALIZER.store(enabled, Ordering::Relaxed);
+}
+
+pub fn show_mapgen_visualizer() -> bool {
+    SHOW_MAPGEN_VISUALIZER.load(Ordering::Relaxed)
+}
+
+/// What every builder produces: the map it carved, where the player starts, what to spawn on it
+/// and the visualizer snapshots taken along the way.
+#[derive(Clone)]
+pub struct BuilderMap {
+    pub map: Map,
+    pub starting_position: Position,
+    pub depth: i32,
+    pub spawn_list: Vec<(usize, String)>,
+    pub history: Vec<Map>,
+}
+
+impl BuilderMap {
+    pub fn new(depth: i32, width: i32, height: i32) -> BuilderMap {
+        BuilderMap {
+            map: Map::new(depth, width, height),
+            starting_position: Position { x: 0, y: 0 },
+            depth,
+            spawn_list: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn take_snapshot(&mut self) {
+        snapshot_map(&mut self.history, &self.map);
+    }
+}
+
+/// A level generator. Implementors only supply the algorithm and hand out their `BuilderMap`;
+/// everything read back out of a finished builder goes through the provided methods.
+pub trait MapBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator);
+    fn build_data(&self) -> &BuilderMap;
+
+    fn get_map(&self) -> Map {
+        self.build_data().map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.build_data().starting_position.clone()
+    }
+
+    fn get_spawn_list(&self) -> &[(usize, String)] {
+        &self.build_data().spawn_list
+    }
+
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.build_data().history.clone()
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for entity in self.get_spawn_list().iter() {
+            spawner::spawn_entity(ecs, entity);
+        }
+    }
+}
+
+/// Picks the generator for a dungeon level. Every new algorithm gets hooked in here, so `State`
+/// never needs to know which one produced the current floor.
+pub fn level_builder(new_depth: i32, rng: &mut RandomNumberGenerator) -> Box<dyn MapBuilder> {
+    // Every fifth floor is a labyrinth, with the tight single-tile variant on every tenth
+    if new_depth % LABYRINTH_DEPTH_INTERVAL == 0 {
+        let cell_size = if new_depth % (LABYRINTH_DEPTH_INTERVAL * 2) == 0 { 1 } else { 2 };
+        return Box::new(MazeBuilder::new(new_depth, MAP_WIDTH, MAP_HEIGHT, cell_size));
+    }
+
+    // The first floors stick to rooms; the organic layouts only show up further down
+    let builder_count = if new_depth < 3 { 2 } else { 9 };
+    let builder: Box<dyn MapBuilder> = match rng.roll_dice(1, builder_count) {
+        1 => Box::new(SimpleMapBuilder::new(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        2 => Box::new(BspDungeonBuilder::new(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        3 => Box::new(CellularAutomataBuilder::new(new_depth, CAVERN_WIDTH, CAVERN_HEIGHT)),
+        4 => Box::new(DrunkardsWalkBuilder::open_area(new_depth, CAVERN_WIDTH, CAVERN_HEIGHT)),
+        5 => Box::new(DrunkardsWalkBuilder::open_halls(new_depth, CAVERN_WIDTH, CAVERN_HEIGHT)),
+        6 => Box::new(DrunkardsWalkBuilder::winding_passages(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        7 => Box::new(DLABuilder::walk_inwards(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        8 => Box::new(DLABuilder::walk_outwards(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+        _ => Box::new(DLABuilder::central_attractor(new_depth, MAP_WIDTH, MAP_HEIGHT)),
+    };
+
+    // Mazes have no open floor to put vaults on, everything else gets a chance of some
+    Box::new(PrefabBuilder::new(builder))
+}
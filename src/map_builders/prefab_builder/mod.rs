@@ -0,0 +1,176 @@
+pub mod prefab_rooms;
+
+use std::collections::HashSet;
+use rltk::{Point, RandomNumberGenerator};
+use crate::map::TileType;
+use crate::map_builders::common::is_fully_connected;
+use crate::map_builders::{BuilderMap, MapBuilder};
+use crate::map_builders::prefab_builder::prefab_rooms::{PrefabRoom, VaultTemplate, VAULTS};
+
+const MAX_VAULTS_PER_LEVEL: i32 = 3;
+const PLACEMENT_ATTEMPTS: usize = 10;
+
+/// Runs another builder, then stamps designer-made vaults onto open floor in its result. A vault
+/// only goes where it and a one tile border around it are floor, and a placement that would cut
+/// part of the level off is undone.
+pub struct PrefabBuilder {
+    data: BuilderMap,
+    previous_builder: Box<dyn MapBuilder>,
+}
+
+impl PrefabBuilder {
+    /// Until it is built, the prefab builder reports the inner builder's empty map, so the size
+    /// always matches whatever the inner builder makes.
+    pub fn new(previous_builder: Box<dyn MapBuilder>) -> PrefabBuilder {
+        PrefabBuilder {
+            data: previous_builder.build_data().clone(),
+            previous_builder,
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        self.previous_builder.build_map(rng);
+        self.data = self.previous_builder.build_data().clone();
+
+        self.apply_vaults(rng);
+    }
+
+    fn apply_vaults(&mut self, rng: &mut RandomNumberGenerator) {
+        let mut candidates: Vec<&PrefabRoom> = VAULTS
+            .iter()
+            .filter(|vault| self.data.depth >= vault.first_depth && self.data.depth <= vault.last_depth)
+            .collect();
+        if candidates.is_empty() { return; }
+
+        let mut used_tiles: HashSet<usize> = HashSet::new();
+        let n_vaults = i32::min(rng.roll_dice(1, MAX_VAULTS_PER_LEVEL), candidates.len() as i32);
+        for _ in 0..n_vaults {
+            let vault_index = (rng.roll_dice(1, candidates.len() as i32) - 1) as usize;
+            let vault = candidates.remove(vault_index);
+            if let Some((template, width, height)) = load_template(&vault.template) {
+                self.place_vault(rng, &template, width, height, &mut used_tiles);
+            }
+        }
+    }
+
+    fn place_vault(&mut self, rng: &mut RandomNumberGenerator, template: &[char], width: i32, height: i32, used_tiles: &mut HashSet<usize>) {
+        let start_idx = self.data.map.xy_idx(self.data.starting_position.x, self.data.starting_position.y);
+
+        let mut positions: Vec<Point> = Vec::new();
+        for y in 1..self.data.map.height - height {
+            for x in 1..self.data.map.width - width {
+                if self.vault_fits(x, y, width, height, start_idx, used_tiles) {
+                    positions.push(Point::new(x, y));
+                }
+            }
+        }
+
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            if positions.is_empty() { return; }
+            let position = positions.remove((rng.roll_dice(1, positions.len() as i32) - 1) as usize);
+
+            let old_tiles = self.data.map.tiles.clone();
+            let mut vault_spawns: Vec<(usize, String)> = Vec::new();
+            let mut vault_tiles: HashSet<usize> = HashSet::new();
+            for ty in 0..height {
+                for tx in 0..width {
+                    let idx = self.data.map.xy_idx(position.x + tx, position.y + ty);
+                    let glyph = template[(ty * width + tx) as usize];
+                    self.data.map.tiles[idx] = if glyph == '#' { TileType::Wall } else { TileType::Floor };
+                    if let Some(name) = glyph_to_spawn(glyph) {
+                        vault_spawns.push((idx, name.to_string()));
+                    }
+                    vault_tiles.insert(idx);
+                }
+            }
+
+            if is_fully_connected(&mut self.data.map, start_idx) {
+                self.data.spawn_list.retain(|(idx, _)| !vault_tiles.contains(idx));
+                self.data.spawn_list.append(&mut vault_spawns);
+                for y in position.y - 1..=position.y + height {
+                    for x in position.x - 1..=position.x + width {
+                        used_tiles.insert(self.data.map.xy_idx(x, y));
+                    }
+                }
+                self.data.take_snapshot();
+                return;
+            }
+
+            self.data.map.tiles = old_tiles;
+        }
+    }
+
+    /// The vault and a one tile border around it must be untouched floor, so the stamped walls
+    /// always have a way around them.
+    fn vault_fits(&self, x: i32, y: i32, width: i32, height: i32, start_idx: usize, used_tiles: &HashSet<usize>) -> bool {
+        for ty in y - 1..=y + height {
+            for tx in x - 1..=x + width {
+                let idx = self.data.map.xy_idx(tx, ty);
+                if self.data.map.tiles[idx] != TileType::Floor || idx == start_idx || used_tiles.contains(&idx) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+impl MapBuilder for PrefabBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.build(rng);
+    }
+
+    fn build_data(&self) -> &BuilderMap {
+        &self.data
+    }
+}
+
+/// Flattens a template into a row-major glyph list. Short ASCII rows are padded with floor. A
+/// REX Paint file that can't be read is logged and gives `None`, so the vault is just skipped.
+fn load_template(template: &VaultTemplate) -> Option<(Vec<char>, i32, i32)> {
+    match template {
+        VaultTemplate::Ascii { template } => {
+            let rows: Vec<&str> = template.lines().filter(|row| !row.is_empty()).collect();
+            let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+            let mut glyphs = Vec::new();
+            for row in rows.iter() {
+                let mut row_glyphs: Vec<char> = row.chars().collect();
+                row_glyphs.resize(width, '.');
+                glyphs.append(&mut row_glyphs);
+            }
+            Some((glyphs, width as i32, rows.len() as i32))
+        }
+        VaultTemplate::Rex { file } => {
+            let xp = match rltk::rex::XpFile::read(&mut &file[..]) {
+                Ok(xp) => xp,
+                Err(e) => {
+                    rltk::console::log(format!("Unable to read vault template, skipping it: {}", e));
+                    return None;
+                }
+            };
+            let Some(layer) = xp.layers.first() else {
+                rltk::console::log("Vault template has no layers, skipping it");
+                return None;
+            };
+            let mut glyphs = Vec::new();
+            for y in 0..layer.height {
+                for x in 0..layer.width {
+                    let cell = layer.get(x, y)?;
+                    glyphs.push(rltk::to_char(cell.ch as u8));
+                }
+            }
+            Some((glyphs, layer.width as i32, layer.height as i32))
+        }
+    }
+}
+
+fn glyph_to_spawn(glyph: char) -> Option<&'static str> {
+    match glyph {
+        'g' => Some("Goblin"),
+        'o' => Some("Orc"),
+        '!' => Some("Health Potion"),
+        '/' => Some("Dagger"),
+        '(' => Some("Shield"),
+        _ => None,
+    }
+}
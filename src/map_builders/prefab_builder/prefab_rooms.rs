@@ -0,0 +1,66 @@
+/// Where a vault's layout comes from. ASCII templates live in this file, one row per line; REX
+/// Paint templates are `.xp` files whose first layer is read glyph by glyph. Either way `#` is a
+/// wall, anything without a meaning of its own is floor, and `g`, `o`, `!`, `/` and `(` spawn a
+/// Goblin, Orc, Health Potion, Dagger and Shield.
+#[derive(PartialEq, Copy, Clone)]
+pub enum VaultTemplate {
+    Ascii { template: &'static str },
+    Rex { file: &'static [u8] },
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub struct PrefabRoom {
+    pub template: VaultTemplate,
+    pub first_depth: i32,
+    pub last_depth: i32,
+}
+
+pub const VAULTS: &[PrefabRoom] = &[GOBLIN_DEN, ORC_CHECKPOINT, QUIET_SHRINE, ARMOURY];
+
+pub const GOBLIN_DEN: PrefabRoom = PrefabRoom {
+    template: VaultTemplate::Ascii { template: GOBLIN_DEN_MAP },
+    first_depth: 1,
+    last_depth: 6,
+};
+
+const GOBLIN_DEN_MAP: &str = "
+##...##
+#..g..#
+..g!g..
+#.....#
+##...##
+";
+
+pub const ORC_CHECKPOINT: PrefabRoom = PrefabRoom {
+    template: VaultTemplate::Ascii { template: ORC_CHECKPOINT_MAP },
+    first_depth: 3,
+    last_depth: 100,
+};
+
+const ORC_CHECKPOINT_MAP: &str = "
+.#.#.
+.#.#.
+.o!o.
+.#.#.
+.#.#.
+";
+
+pub const QUIET_SHRINE: PrefabRoom = PrefabRoom {
+    template: VaultTemplate::Ascii { template: QUIET_SHRINE_MAP },
+    first_depth: 1,
+    last_depth: 100,
+};
+
+const QUIET_SHRINE_MAP: &str = "
+.....
+.#.#.
+..!..
+.#.#.
+.....
+";
+
+pub const ARMOURY: PrefabRoom = PrefabRoom {
+    template: VaultTemplate::Rex { file: include_bytes!("../../resources/armoury.xp") },
+    first_depth: 4,
+    last_depth: 100,
+};
@@ -0,0 +1,111 @@
+use rltk::RandomNumberGenerator;
+use crate::components::Position;
+use crate::map::TileType;
+use crate::map_builders::common::{apply_horizontal_tunnel, apply_room_to_map, apply_vertical_tunnel};
+use crate::map_builders::{BuilderMap, MapBuilder};
+use crate::rect::Rect;
+use crate::spawner;
+
+const MAX_ROOMS: i32 = 30;
+const MIN_SIZE: i32 = 6;
+const MAX_SIZE: i32 = 10;
+
+/// The classic generator: randomly placed rectangular rooms joined by L-shaped corridors.
+pub struct SimpleMapBuilder {
+    data: BuilderMap,
+}
+
+impl SimpleMapBuilder {
+    pub fn new(depth: i32, width: i32, height: i32) -> SimpleMapBuilder {
+        SimpleMapBuilder {
+            data: BuilderMap::new(depth, width, height),
+        }
+    }
+
+    fn rooms_and_corridors(&mut self, rng: &mut RandomNumberGenerator) {
+        for _ in 0..MAX_ROOMS {
+            let w = rng.range(MIN_SIZE, MAX_SIZE);
+            let h = rng.range(MIN_SIZE, MAX_SIZE);
+            let x = rng.roll_dice(1, self.data.map.width - w - 1) - 1;
+            let y = rng.roll_dice(1, self.data.map.height - h - 1) - 1;
+            let new_room = Rect::new(x, y, w, h);
+            let mut ok = true;
+            for other_room in self.data.map.rooms.iter() {
+                if new_room.intersect(other_room) { ok = false }
+            }
+            if ok {
+                apply_room_to_map(&mut self.data.map, &new_room);
+
+                if !self.data.map.rooms.is_empty() {
+                    let (new_x, new_y) = new_room.center();
+                    let (prev_x, prev_y) = self.data.map.rooms[self.data.map.rooms.len() - 1].center();
+                    if rng.range(0, 2) == 1 {
+                        apply_horizontal_tunnel(&mut self.data.map, prev_x, new_x, prev_y);
+                        apply_vertical_tunnel(&mut self.data.map, prev_y, new_y, new_x);
+                    } else {
+                        apply_vertical_tunnel(&mut self.data.map, prev_y, new_y, prev_x);
+                        apply_horizontal_tunnel(&mut self.data.map, prev_x, new_x, new_y);
+                    }
+                }
+
+                self.data.map.rooms.push(new_room);
+                self.data.take_snapshot();
+            }
+        }
+
+        for doorway in self.doorways() {
+            self.data.spawn_list.push((doorway, "Door".to_string()));
+        }
+
+        let stairs_position = self.data.map.rooms[self.data.map.rooms.len() - 1].center();
+        let stairs_idx = self.data.map.xy_idx(stairs_position.0, stairs_position.1);
+        self.data.map.tiles[stairs_idx] = TileType::DownStairs;
+        self.data.take_snapshot();
+
+        let (start_x, start_y) = self.data.map.rooms[0].center();
+        self.data.starting_position = Position { x: start_x, y: start_y };
+
+        // Spawn bad guys everywhere except the starting room
+        for room in self.data.map.rooms.iter().skip(1) {
+            spawner::spawn_room(&self.data.map, rng, room, self.data.depth, &mut self.data.spawn_list);
+        }
+    }
+
+    /// Any floor on a room's outer wall is where a corridor broke through. Those squeezed between
+    /// two walls get a door; wider gaps, where a corridor runs along the wall, are left open. Rooms
+    /// one tile apart would get a door on each wall, so only the first of two neighbours is kept.
+    fn doorways(&self) -> Vec<usize> {
+        let width = self.data.map.width as usize;
+        let mut doorways: Vec<usize> = Vec::new();
+        for room in self.data.map.rooms.iter() {
+            for y in room.y1..=room.y2 + 1 {
+                for x in room.x1..=room.x2 + 1 {
+                    let on_wall = x == room.x1 || x == room.x2 + 1 || y == room.y1 || y == room.y2 + 1;
+                    let idx = self.data.map.xy_idx(x, y);
+                    let next_to_door = doorways.iter().any(|d| *d == idx || *d + 1 == idx || *d == idx + 1 || *d + width == idx || *d == idx + width);
+                    if on_wall && self.is_doorway(x, y) && !next_to_door {
+                        doorways.push(idx);
+                    }
+                }
+            }
+        }
+        doorways
+    }
+
+    fn is_doorway(&self, x: i32, y: i32) -> bool {
+        if x < 1 || x > self.data.map.width - 2 || y < 1 || y > self.data.map.height - 2 { return false; }
+        let is_wall = |x: i32, y: i32| self.data.map.tiles[self.data.map.xy_idx(x, y)] == TileType::Wall;
+        self.data.map.tiles[self.data.map.xy_idx(x, y)] == TileType::Floor
+            && ((is_wall(x - 1, y) && is_wall(x + 1, y)) || (is_wall(x, y - 1) && is_wall(x, y + 1)))
+    }
+}
+
+impl MapBuilder for SimpleMapBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator) {
+        self.rooms_and_corridors(rng);
+    }
+
+    fn build_data(&self) -> &BuilderMap {
+        &self.data
+    }
+}
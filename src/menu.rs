@@ -1,5 +1,5 @@
 use rltk::{RGB, Rltk, VirtualKeyCode};
-use crate::gui::{MainMenuResult, MainMenuSelection};
+use crate::gui::{MainMenuResult, MainMenuSelection, SeedMenuResult};
 use crate::state::{RunState, State};
 use crate::systems;
 
@@ -68,3 +68,40 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
 
     MainMenuResult::NoSelection { selected: MainMenuSelection::NewGame }
 }
+
+pub fn seed_menu(ctx: &mut Rltk, seed: u64) -> SeedMenuResult {
+    ctx.print_color_centered(15, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), "Rust Roguelike Tutorial");
+    ctx.print_color_centered(22, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), "Dungeon seed:");
+    ctx.print_color_centered(24, RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK), seed.to_string());
+    ctx.print_color_centered(27, RGB::named(rltk::GREY), RGB::named(rltk::BLACK), "Type a seed to replay a run, BACKSPACE to erase");
+    ctx.print_color_centered(28, RGB::named(rltk::GREY), RGB::named(rltk::BLACK), "ENTER to begin, ESCAPE to go back");
+
+    match ctx.key {
+        None => SeedMenuResult::NoSelection { seed },
+        Some(key) => {
+            match key {
+                VirtualKeyCode::Escape => SeedMenuResult::Cancel,
+                VirtualKeyCode::Return => SeedMenuResult::Selected { seed },
+                VirtualKeyCode::Back => SeedMenuResult::NoSelection { seed: seed / 10 },
+                _ => {
+                    let digit = match key {
+                        VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => Some(0),
+                        VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => Some(1),
+                        VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => Some(2),
+                        VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => Some(3),
+                        VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => Some(4),
+                        VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => Some(5),
+                        VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => Some(6),
+                        VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => Some(7),
+                        VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => Some(8),
+                        VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => Some(9),
+                        _ => None,
+                    };
+                    // Digits that would overflow the seed are ignored
+                    let new_seed = digit.and_then(|d| seed.checked_mul(10).and_then(|s| s.checked_add(d)));
+                    SeedMenuResult::NoSelection { seed: new_seed.unwrap_or(seed) }
+                }
+            }
+        }
+    }
+}
@@ -1,19 +1,44 @@
-use crate::components::{CombatStats, Item, Monster, Player, Position, Viewshed, WantsToMelee, WantsToPickupItem};
+use crate::components::{Ammunition, Attributes, BlocksTile, BlocksVisibility, CombatStats, Door, EntityMoved, Equipped, Experience, InBackpack, Item, Monster, MyTurn, Player, Position, RangedWeapon, Renderable, Skills, StatusEffects, StatusKind, Viewshed, WantsToMelee, WantsToPickupItem};
 use crate::game_log::GameLog;
-use crate::map::{Map, TileType};
+use crate::gui::LevelUpChoice;
+use crate::map::{open_door, Map, TileType};
 use crate::state::{RunState, State};
 
-use rltk::{Point, Rltk, VirtualKeyCode};
+use rltk::{Point, RandomNumberGenerator, Rltk, VirtualKeyCode};
 use specs::prelude::*;
 
+/// A confused player only goes where they meant to half the time.
+fn confused_step(ecs: &World, delta_x: i32, delta_y: i32) -> (i32, i32) {
+    let player_entity = ecs.fetch::<Entity>();
+    let confused = ecs.read_storage::<StatusEffects>().get(*player_entity).is_some_and(|status| status.has(StatusKind::Confusion));
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    if !confused || rng.roll_dice(1, 2) == 1 {
+        return (delta_x, delta_y);
+    }
+
+    ecs.fetch_mut::<GameLog>().entries.push("You stumble around in confusion.".to_string());
+    loop {
+        let (dx, dy) = (rng.roll_dice(1, 3) - 2, rng.roll_dice(1, 3) - 2);
+        if dx != 0 || dy != 0 {
+            return (dx, dy);
+        }
+    }
+}
+
 fn try_move(delta_x: i32, delta_y: i32, ecs: &mut World) {
+    let (delta_x, delta_y) = confused_step(ecs, delta_x, delta_y);
     let mut positions = ecs.write_storage::<Position>();
     let mut players = ecs.write_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
     let combat_stats = ecs.write_storage::<CombatStats>();
     let entities = ecs.entities();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
-    let map = ecs.fetch::<Map>();
+    let mut doors = ecs.write_storage::<Door>();
+    let mut blocks_movement = ecs.write_storage::<BlocksTile>();
+    let mut blocks_visibility = ecs.write_storage::<BlocksVisibility>();
+    let mut renderables = ecs.write_storage::<Renderable>();
+    let mut entity_moved = ecs.write_storage::<EntityMoved>();
+    let mut map = ecs.fetch_mut::<Map>();
 
     for (entity, _player, pos, viewshed) in (&entities, &mut players, &mut positions, &mut viewsheds).join() {
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
@@ -29,15 +54,22 @@ fn try_move(delta_x: i32, delta_y: i32, ecs: &mut World) {
             }
         }
 
-        let mut ppos = ecs.write_resource::<Point>();
-        ppos.x = pos.x;
-        ppos.y = pos.y;
+        // Walking into a closed door opens it, which takes the move
+        if open_door(&mut map, destination_idx, &mut doors, &mut blocks_movement, &mut blocks_visibility, &mut renderables) {
+            viewshed.dirty = true;
+            return;
+        }
 
         if !map.blocked[destination_idx] {
-            pos.x = (pos.x + delta_x).clamp(0, 79);
-            pos.y = (pos.y + delta_y).clamp(0, 49);
+            pos.x = (pos.x + delta_x).clamp(0, map.width - 1);
+            pos.y = (pos.y + delta_y).clamp(0, map.height - 1);
 
             viewshed.dirty = true;
+            entity_moved.insert(entity, EntityMoved {}).expect("Unable to insert marker");
+
+            let mut ppos = ecs.write_resource::<Point>();
+            ppos.x = pos.x;
+            ppos.y = pos.y;
         }
     }
 }
@@ -77,6 +109,8 @@ pub fn read_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
 
             VirtualKeyCode::G => get_item(&mut gs.ecs),
 
+            VirtualKeyCode::F => return fire(&mut gs.ecs),
+
             VirtualKeyCode::I => return RunState::ShowInventory,
 
             VirtualKeyCode::O => return RunState::ShowDropItem,
@@ -88,6 +122,11 @@ pub fn read_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
                     return RunState::NextLevel;
                 }
             }
+            VirtualKeyCode::Comma => {
+                if try_previous_level(&mut gs.ecs) {
+                    return RunState::PreviousLevel;
+                }
+            }
 
             // Skip Turn
             VirtualKeyCode::Numpad5 => return skip_turn(&mut gs.ecs),
@@ -127,6 +166,106 @@ fn get_item(ecs: &mut World) {
     }
 }
 
+/// Starts aiming the equipped ranged weapon, if there is one and something to shoot from it.
+fn fire(ecs: &mut World) -> RunState {
+    let player_entity = ecs.fetch::<Entity>();
+    let ranged_weapons = ecs.read_storage::<RangedWeapon>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let ammunition = ecs.read_storage::<Ammunition>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let mut game_log = ecs.fetch_mut::<GameLog>();
+
+    let Some((weapon, _equipped_by)) = (&ranged_weapons, &equipped).join().find(|(_weapon, equipped_by)| equipped_by.owner == *player_entity) else {
+        game_log.entries.push("You have nothing to shoot with.".to_string());
+        return RunState::AwaitingInput;
+    };
+    if !(&ammunition, &backpack).join().any(|(ammo, pack)| pack.owner == *player_entity && ammo.kind == weapon.ammo) {
+        game_log.entries.push(format!("You have no {} to shoot.", weapon.ammo.name()));
+        return RunState::AwaitingInput;
+    }
+
+    RunState::ShowFireTargeting { range: weapon.range }
+}
+
+pub fn try_previous_level(ecs: &mut World) -> bool {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+    if map.tiles[player_idx] == TileType::UpStairs {
+        true
+    } else {
+        let mut game_log = ecs.fetch_mut::<GameLog>();
+        game_log.entries.push("There is no way up from here.".to_string());
+        false
+    }
+}
+
+/// Every level is worth this much max hp (plus the fitness bonus) and a point of melee skill, on
+/// top of the attribute the player picks.
+const HP_PER_LEVEL: i32 = 5;
+
+pub fn can_level_up(ecs: &World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.read_storage::<Experience>().get(*player_entity).is_some_and(|exp| exp.can_level_up())
+}
+
+/// Spends the experience for one level, improving the player's stats and healing them fully.
+pub fn level_up(ecs: &mut World, choice: LevelUpChoice) {
+    let player_entity = ecs.fetch::<Entity>();
+    let mut experience = ecs.write_storage::<Experience>();
+    let mut combat_stats = ecs.write_storage::<CombatStats>();
+    let mut attributes = ecs.write_storage::<Attributes>();
+    let mut skills = ecs.write_storage::<Skills>();
+    let (Some(exp), Some(stats), Some(attr), Some(skill)) = (
+        experience.get_mut(*player_entity),
+        combat_stats.get_mut(*player_entity),
+        attributes.get_mut(*player_entity),
+        skills.get_mut(*player_entity),
+    ) else { return; };
+
+    exp.xp -= exp.xp_to_next_level();
+    exp.level += 1;
+    match choice {
+        LevelUpChoice::Might => attr.might += 1,
+        LevelUpChoice::Fitness => attr.fitness += 1,
+        LevelUpChoice::Quickness => attr.quickness += 1,
+        LevelUpChoice::Intelligence => attr.intelligence += 1,
+    }
+    skill.melee += 1;
+    skill.ranged += 1;
+    stats.max_hp += i32::max(1, HP_PER_LEVEL + Attributes::bonus(attr.fitness));
+    stats.hp = stats.max_hp;
+
+    let mut game_log = ecs.fetch_mut::<GameLog>();
+    game_log.entries.push(format!("Congratulations, you are now level {}!", exp.level));
+}
+
+/// True if a status effect costs the player this turn, in which case the turn passes without
+/// waiting for input.
+pub fn loses_turn(ecs: &mut World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    let statuses = ecs.read_storage::<StatusEffects>();
+    let Some(status) = statuses.get(*player_entity) else { return false; };
+    if !status.loses_turn() {
+        return false;
+    }
+
+    ecs.fetch_mut::<GameLog>().entries.push("You are stunned, and can't act!".to_string());
+    true
+}
+
+/// True if the clock has come round to the player.
+pub fn has_initiative(ecs: &World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.read_storage::<MyTurn>().get(*player_entity).is_some()
+}
+
+/// The player's action has been played out, so hand the clock back to everyone else.
+pub fn end_turn(ecs: &mut World) {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.write_storage::<MyTurn>().remove(*player_entity);
+}
+
 pub fn try_next_level(ecs: &mut World) -> bool {
     let player_pos = ecs.fetch::<Point>();
     let map = ecs.fetch::<Map>();
@@ -1,49 +1,242 @@
 use rltk::RandomNumberGenerator;
 
-pub struct RandomEntry {
-    name: String,
-    weight: i32,
+/// What an entry hands back when it's rolled: a value, or another roll on a table of its own.
+enum Outcome<T> {
+    Value(T),
+    Table(RandomTable<T>),
 }
 
-impl RandomEntry {
-    pub fn new<S: ToString>(name: S, weight: i32) -> RandomEntry {
-        RandomEntry { name: name.to_string(), weight }
-    }
+struct RandomEntry<T> {
+    outcome: Outcome<T>,
+    weight: i32,
 }
 
-#[derive(Default)]
-pub struct RandomTable {
-    entries: Vec<RandomEntry>,
+/// A weighted table of `T`s. Entries can be whole sub-tables ("any scroll"), which take their
+/// share of the rolls by their own weight and then split it between their entries.
+pub struct RandomTable<T> {
+    entries: Vec<RandomEntry<T>>,
     total_weight: i32,
 }
 
-impl RandomTable {
-    pub fn new() -> RandomTable {
+impl<T> Default for RandomTable<T> {
+    fn default() -> Self {
+        RandomTable::new()
+    }
+}
+
+impl<T> RandomTable<T> {
+    pub fn new() -> RandomTable<T> {
         RandomTable { entries: Vec::new(), total_weight: 0 }
     }
 
-    pub fn add<S: ToString>(mut self, name: S, weight: i32) -> RandomTable {
+    /// Adds `value` with `weight`. Entries that weigh nothing can never come up, so are left out.
+    pub fn add(self, value: T, weight: i32) -> RandomTable<T> {
+        self.add_entry(Outcome::Value(value), weight)
+    }
+
+    /// Adds a whole table as one entry. Empty tables have nothing to give, so are left out.
+    pub fn add_table(self, table: RandomTable<T>, weight: i32) -> RandomTable<T> {
+        if table.is_empty() { return self; }
+        self.add_entry(Outcome::Table(table), weight)
+    }
+
+    fn add_entry(mut self, outcome: Outcome<T>, weight: i32) -> RandomTable<T> {
         if weight > 0 {
             self.total_weight += weight;
-            self.entries.push(RandomEntry::new(name.to_string(), weight));
+            self.entries.push(RandomEntry { outcome, weight });
         }
         self
     }
 
-    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> String {
-        if self.total_weight == 0 { return "None".to_string(); }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Picks an entry by weight, or `None` if the table is empty.
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> Option<&T> {
+        let index = self.pick(rng)?;
+        match &self.entries[index].outcome {
+            Outcome::Value(value) => Some(value),
+            Outcome::Table(table) => table.roll(rng),
+        }
+    }
+
+    fn pick(&self, rng: &mut RandomNumberGenerator) -> Option<usize> {
+        if self.total_weight == 0 { return None; }
         let mut roll = rng.roll_dice(1, self.total_weight) - 1;
-        let mut index: usize = 0;
+        for (index, entry) in self.entries.iter().enumerate() {
+            if roll < entry.weight {
+                return Some(index);
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
 
-        while roll > 0 {
-            if roll < self.entries[index].weight {
-                return self.entries[index].name.clone();
+    /// Rolls up to `count` times, taking each result out of the table so nothing comes up twice.
+    /// Stops early if the table runs out.
+    pub fn roll_many(&self, rng: &mut RandomNumberGenerator, count: usize) -> Vec<&T>
+    where
+        T: PartialEq,
+    {
+        let mut remaining = self.by_ref();
+        let mut rolled = Vec::new();
+        while rolled.len() < count {
+            let Some(value) = remaining.roll(rng).copied() else { break };
+            remaining.remove(&value);
+            rolled.push(value);
+        }
+        rolled
+    }
+
+    /// Takes every entry for `value` out of the table, including those in sub-tables. A sub-table
+    /// left empty goes too; one with entries left keeps its weight.
+    pub fn remove(&mut self, value: &T)
+    where
+        T: PartialEq,
+    {
+        for entry in self.entries.iter_mut() {
+            if let Outcome::Table(table) = &mut entry.outcome {
+                table.remove(value);
+            }
+        }
+        self.entries.retain(|entry| match &entry.outcome {
+            Outcome::Value(entry_value) => entry_value != value,
+            Outcome::Table(table) => !table.is_empty(),
+        });
+        self.total_weight = self.entries.iter().map(|entry| entry.weight).sum();
+    }
+
+    /// The chance of each value coming up on a single roll, with sub-tables flattened out.
+    pub fn odds(&self) -> Vec<(&T, f32)> {
+        let mut odds = Vec::new();
+        for entry in self.entries.iter() {
+            let chance = entry.weight as f32 / self.total_weight as f32;
+            match &entry.outcome {
+                Outcome::Value(value) => odds.push((value, chance)),
+                Outcome::Table(table) => {
+                    odds.extend(table.odds().into_iter().map(|(value, sub_chance)| (value, chance * sub_chance)));
+                }
             }
+        }
+        odds
+    }
+
+    /// The same table, borrowing its values rather than owning them.
+    fn by_ref(&self) -> RandomTable<&T> {
+        RandomTable {
+            entries: self.entries.iter().map(|entry| RandomEntry {
+                outcome: match &entry.outcome {
+                    Outcome::Value(value) => Outcome::Value(value),
+                    Outcome::Table(table) => Outcome::Table(table.by_ref()),
+                },
+                weight: entry.weight,
+            }).collect(),
+            total_weight: self.total_weight,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLLS: usize = 100_000;
+    /// How far an observed share may stray from its expected share over `ROLLS` rolls.
+    const TOLERANCE: f32 = 0.01;
+
+    fn share(table: &RandomTable<&str>, rng: &mut RandomNumberGenerator, value: &str) -> f32 {
+        let hits = (0..ROLLS).filter(|_| table.roll(rng) == Some(&value)).count();
+        hits as f32 / ROLLS as f32
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < TOLERANCE, "expected {} but got {}", expected, actual);
+    }
+
+    #[test]
+    fn single_entry_always_rolls() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let table = RandomTable::new().add("Goblin", 1);
+        for _ in 0..100 {
+            assert_eq!(table.roll(&mut rng), Some(&"Goblin"));
+        }
+    }
+
+    #[test]
+    fn empty_table_rolls_nothing() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let table = RandomTable::new().add("Goblin", 0).add("Orc", -3);
+        assert!(table.is_empty());
+        assert_eq!(table.roll(&mut rng), None);
+    }
+
+    #[test]
+    fn rolls_follow_the_weights() {
+        let mut rng = RandomNumberGenerator::seeded(2);
+        let table = RandomTable::new().add("Goblin", 1).add("Orc", 2).add("Troll", 7);
+        assert_close(share(&table, &mut rng, "Goblin"), 0.1);
+        assert_close(share(&table, &mut rng, "Orc"), 0.2);
+        assert_close(share(&table, &mut rng, "Troll"), 0.7);
+    }
+
+    #[test]
+    fn sub_tables_split_their_share() {
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let scrolls = RandomTable::new().add("Fireball", 1).add("Confusion", 3);
+        let table = RandomTable::new().add("Goblin", 1).add_table(scrolls, 1);
+        assert_close(share(&table, &mut rng, "Goblin"), 0.5);
+        assert_close(share(&table, &mut rng, "Fireball"), 0.125);
+        assert_close(share(&table, &mut rng, "Confusion"), 0.375);
+    }
 
-            roll -= self.entries[index].weight;
-            index += 1;
+    #[test]
+    fn odds_match_the_weights() {
+        let scrolls = RandomTable::new().add("Fireball", 1).add("Confusion", 3);
+        let table = RandomTable::new().add("Goblin", 2).add_table(scrolls, 2).add_table(RandomTable::new(), 5);
+        let odds = table.odds();
+        assert_eq!(odds, vec![(&"Goblin", 0.5), (&"Fireball", 0.125), (&"Confusion", 0.375)]);
+    }
+
+    #[test]
+    fn removed_entries_never_roll() {
+        let mut rng = RandomNumberGenerator::seeded(4);
+        let scrolls = RandomTable::new().add("Fireball", 1).add("Confusion", 1);
+        let mut table = RandomTable::new().add("Goblin", 1).add("Orc", 1).add_table(scrolls, 2);
+        table.remove(&"Orc");
+        table.remove(&"Fireball");
+        assert_close(share(&table, &mut rng, "Orc"), 0.0);
+        assert_close(share(&table, &mut rng, "Fireball"), 0.0);
+        assert_close(share(&table, &mut rng, "Goblin"), 1.0 / 3.0);
+        assert_close(share(&table, &mut rng, "Confusion"), 2.0 / 3.0);
+
+        table.remove(&"Confusion");
+        table.remove(&"Goblin");
+        assert!(table.is_empty());
+        assert_eq!(table.roll(&mut rng), None);
+    }
+
+    #[test]
+    fn roll_many_never_repeats() {
+        let mut rng = RandomNumberGenerator::seeded(5);
+        let scrolls = RandomTable::new().add("Fireball", 1).add("Confusion", 1);
+        let table = RandomTable::new().add("Goblin", 5).add("Orc", 1).add_table(scrolls, 1);
+        for _ in 0..1000 {
+            let mut rolled = table.roll_many(&mut rng, 10);
+            assert_eq!(rolled.len(), 4);
+            rolled.sort();
+            rolled.dedup();
+            assert_eq!(rolled.len(), 4);
         }
+        // The table itself is left as it was
+        assert_eq!(table.odds().len(), 4);
+    }
 
-        "None".to_string()
+    #[test]
+    fn roll_many_follows_the_weights() {
+        let mut rng = RandomNumberGenerator::seeded(6);
+        let table = RandomTable::new().add("Goblin", 3).add("Orc", 1);
+        let goblin_first = (0..ROLLS).filter(|_| table.roll_many(&mut rng, 1) == vec![&"Goblin"]).count();
+        assert_close(goblin_first as f32 / ROLLS as f32, 0.75);
     }
 }
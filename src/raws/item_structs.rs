@@ -0,0 +1,79 @@
+use serde::Deserialize;
+use crate::components::{AmmoKind, StatusKind};
+
+#[derive(Deserialize, Debug)]
+pub struct RawItem {
+    pub name: String,
+    pub renderable: Option<RawRenderable>,
+    pub consumable: Option<RawConsumable>,
+    pub weapon: Option<RawWeapon>,
+    pub shield: Option<RawShield>,
+    pub ranged_weapon: Option<RawRangedWeapon>,
+    pub ammo: Option<RawAmmo>,
+}
+
+/// How something is drawn. Colours are HTML-style hex codes, e.g. `"#FF0000"`.
+#[derive(Deserialize, Debug)]
+pub struct RawRenderable {
+    pub glyph: String,
+    pub fg: String,
+    pub bg: String,
+    pub order: i32,
+}
+
+/// Used up when used. Each effect that is present applies.
+#[derive(Deserialize, Debug)]
+pub struct RawConsumable {
+    /// Targeted from this many tiles away; without it the item is used on yourself.
+    pub range: Option<i32>,
+    pub healing: Option<i32>,
+    pub damage: Option<i32>,
+    pub area_of_effect: Option<i32>,
+    pub status: Option<RawStatus>,
+    /// Turns of being well fed.
+    pub food: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawStatus {
+    pub kind: StatusKind,
+    pub turns: i32,
+    #[serde(default = "default_magnitude")]
+    pub magnitude: i32,
+}
+
+fn default_magnitude() -> i32 {
+    1
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawWeapon {
+    /// Dice notation, e.g. `"1d8+1"`.
+    pub damage: String,
+    #[serde(default)]
+    pub hit_bonus: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawShield {
+    pub defense_bonus: i32,
+    #[serde(default)]
+    pub speed_penalty: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawRangedWeapon {
+    pub range: i32,
+    pub ammo: AmmoKind,
+    /// Dice notation, e.g. `"1d8+1"`.
+    pub damage: String,
+    #[serde(default)]
+    pub hit_bonus: i32,
+}
+
+/// A stack of `count` shots.
+#[derive(Deserialize, Debug)]
+pub struct RawAmmo {
+    pub kind: AmmoKind,
+    pub count: i32,
+}
@@ -0,0 +1,34 @@
+use serde::Deserialize;
+use super::item_structs::RawRenderable;
+
+#[derive(Deserialize, Debug)]
+pub struct RawMob {
+    pub name: String,
+    pub renderable: Option<RawRenderable>,
+    #[serde(default)]
+    pub blocks_tile: bool,
+    pub vision_range: i32,
+    pub hp: i32,
+    pub attributes: RawAttributes,
+    pub skills: RawSkills,
+    /// Experience awarded for killing it.
+    pub xp: i32,
+    /// Defaults to the same base speed as the player.
+    pub speed: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawAttributes {
+    pub might: i32,
+    pub fitness: i32,
+    pub quickness: i32,
+    pub intelligence: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RawSkills {
+    pub melee: i32,
+    pub ranged: i32,
+    pub defense: i32,
+    pub magic: i32,
+}
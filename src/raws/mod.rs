@@ -0,0 +1,46 @@
+mod item_structs;
+mod mob_structs;
+mod prop_structs;
+mod rawmaster;
+mod spawn_table_structs;
+
+pub use rawmaster::*;
+
+use item_structs::RawItem;
+use mob_structs::RawMob;
+use prop_structs::RawProp;
+use serde::Deserialize;
+use spawn_table_structs::SpawnTableEntry;
+use std::sync::OnceLock;
+
+/// Where designers edit the entity definitions. Read at startup, so changes only need a restart.
+const RAWS_PATH: &str = "./raws/spawns.json";
+
+/// A copy of the definitions built into the binary, for when the file isn't there to be read
+/// (running from somewhere else, or on the web).
+const EMBEDDED_RAWS: &str = include_str!("../../raws/spawns.json");
+
+/// Everything the game can spawn, as read from the raws file.
+#[derive(Deserialize, Debug)]
+pub struct Raws {
+    pub mobs: Vec<RawMob>,
+    pub items: Vec<RawItem>,
+    pub props: Vec<RawProp>,
+    pub spawn_table: Vec<SpawnTableEntry>,
+}
+
+/// The loaded raws, read the first time anything asks for them.
+pub fn raws() -> &'static RawMaster {
+    static RAWS: OnceLock<RawMaster> = OnceLock::new();
+    RAWS.get_or_init(|| RawMaster::load(parse_raws()))
+}
+
+fn parse_raws() -> Raws {
+    if let Ok(data) = std::fs::read_to_string(RAWS_PATH) {
+        match serde_json::from_str(&data) {
+            Ok(raws) => return raws,
+            Err(e) => rltk::console::log(format!("Unable to parse {}, using the built-in raws: {}", RAWS_PATH, e)),
+        }
+    }
+    serde_json::from_str(EMBEDDED_RAWS).expect("Unable to parse the built-in raws")
+}
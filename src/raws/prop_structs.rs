@@ -0,0 +1,31 @@
+use serde::Deserialize;
+use super::item_structs::{RawRenderable, RawStatus};
+
+/// Scenery: doors, traps and the like.
+#[derive(Deserialize, Debug)]
+pub struct RawProp {
+    pub name: String,
+    pub renderable: Option<RawRenderable>,
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub blocks_tile: bool,
+    #[serde(default)]
+    pub blocks_visibility: bool,
+    /// Spawns as a closed door.
+    #[serde(default)]
+    pub door: bool,
+    pub trap: Option<RawTrap>,
+}
+
+/// What happens to whoever steps on it.
+#[derive(Deserialize, Debug)]
+pub struct RawTrap {
+    pub damage: Option<i32>,
+    pub status: Option<RawStatus>,
+    #[serde(default)]
+    pub teleport: bool,
+    /// Destroyed after it goes off once.
+    #[serde(default)]
+    pub single_activation: bool,
+}
@@ -0,0 +1,227 @@
+use std::collections::{HashMap, HashSet};
+use rltk::RGB;
+use specs::prelude::*;
+use specs::saveload::{MarkedBuilder, SimpleMarker};
+use crate::components::*;
+use crate::random_table::RandomTable;
+use crate::spawner::BASE_SPEED;
+use super::item_structs::{RawRenderable, RawStatus};
+use super::spawn_table_structs::SpawnTableEntry;
+use super::Raws;
+
+/// The raws, indexed by name.
+pub struct RawMaster {
+    raws: Raws,
+    mob_index: HashMap<String, usize>,
+    item_index: HashMap<String, usize>,
+    prop_index: HashMap<String, usize>,
+}
+
+impl RawMaster {
+    pub fn load(raws: Raws) -> RawMaster {
+        let mut used_names: HashSet<String> = HashSet::new();
+        let mob_index = index_names(raws.mobs.iter().map(|mob| &mob.name), &mut used_names);
+        let item_index = index_names(raws.items.iter().map(|item| &item.name), &mut used_names);
+        let prop_index = index_names(raws.props.iter().map(|prop| &prop.name), &mut used_names);
+
+        check_spawn_table(&raws.spawn_table, &used_names);
+
+        RawMaster { raws, mob_index, item_index, prop_index }
+    }
+
+    /// The spawn table for a level at `map_depth`.
+    pub fn room_table(&self, map_depth: i32) -> RandomTable<&str> {
+        spawn_table(&self.raws.spawn_table, map_depth)
+    }
+
+    /// Creates the entity called `name` at `(x, y)`. Names the raws don't know spawn nothing;
+    /// the spawn table is checked against the raws when they are loaded.
+    pub fn spawn_named_entity(&self, ecs: &mut World, name: &str, x: i32, y: i32) {
+        if let Some(idx) = self.mob_index.get(name) {
+            self.spawn_mob(ecs, *idx, x, y);
+        } else if let Some(idx) = self.item_index.get(name) {
+            self.spawn_item(ecs, *idx, x, y);
+        } else if let Some(idx) = self.prop_index.get(name) {
+            self.spawn_prop(ecs, *idx, x, y);
+        }
+    }
+
+    fn spawn_mob(&self, ecs: &mut World, idx: usize, x: i32, y: i32) {
+        let mob = &self.raws.mobs[idx];
+        let mut eb = spawn_base(ecs, &mob.name, &mob.renderable, x, y)
+            .with(Monster {})
+            .with(Viewshed { visible_tiles: Vec::new(), range: mob.vision_range, dirty: true })
+            .with(CombatStats { max_hp: mob.hp, hp: mob.hp })
+            .with(Attributes {
+                might: mob.attributes.might,
+                fitness: mob.attributes.fitness,
+                quickness: mob.attributes.quickness,
+                intelligence: mob.attributes.intelligence,
+            })
+            .with(Skills { melee: mob.skills.melee, ranged: mob.skills.ranged, defense: mob.skills.defense, magic: mob.skills.magic })
+            .with(Initiative { speed: mob.speed.unwrap_or(BASE_SPEED), energy: 0 })
+            .with(GrantsExperience { xp: mob.xp });
+        if mob.blocks_tile { eb = eb.with(BlocksTile {}); }
+        eb.build();
+    }
+
+    fn spawn_item(&self, ecs: &mut World, idx: usize, x: i32, y: i32) {
+        let item = &self.raws.items[idx];
+        let mut eb = spawn_base(ecs, &item.name, &item.renderable, x, y).with(Item {});
+
+        if let Some(consumable) = &item.consumable {
+            eb = eb.with(Consumable {});
+            if let Some(range) = consumable.range { eb = eb.with(Ranged { range }); }
+            if let Some(heal_amount) = consumable.healing { eb = eb.with(ProvidesHealing { heal_amount }); }
+            if let Some(damage) = consumable.damage { eb = eb.with(InflictsDamage { damage }); }
+            if let Some(radius) = consumable.area_of_effect { eb = eb.with(AreaOfEffect { radius }); }
+            if let Some(status) = &consumable.status { eb = eb.with(causes_status(status)); }
+            if let Some(turns) = consumable.food { eb = eb.with(ProvidesFood { turns }); }
+        }
+
+        if let Some(weapon) = &item.weapon {
+            let (damage_n_dice, damage_die_type, damage_bonus) = parse_dice_string(&weapon.damage);
+            eb = eb
+                .with(Equippable { slot: EquipmentSlot::Melee })
+                .with(MeleeWeapon { damage_n_dice, damage_die_type, damage_bonus, hit_bonus: weapon.hit_bonus });
+        }
+
+        if let Some(shield) = &item.shield {
+            eb = eb
+                .with(Equippable { slot: EquipmentSlot::Shield })
+                .with(DefenseBonus { defense: shield.defense_bonus });
+            if shield.speed_penalty > 0 { eb = eb.with(SpeedPenalty { penalty: shield.speed_penalty }); }
+        }
+
+        if let Some(weapon) = &item.ranged_weapon {
+            let (damage_n_dice, damage_die_type, damage_bonus) = parse_dice_string(&weapon.damage);
+            eb = eb
+                .with(Equippable { slot: EquipmentSlot::Ranged })
+                .with(RangedWeapon { range: weapon.range, ammo: weapon.ammo, damage_n_dice, damage_die_type, damage_bonus, hit_bonus: weapon.hit_bonus });
+        }
+
+        if let Some(ammo) = &item.ammo {
+            eb = eb.with(Ammunition { kind: ammo.kind, count: ammo.count });
+        }
+
+        eb.build();
+    }
+
+    fn spawn_prop(&self, ecs: &mut World, idx: usize, x: i32, y: i32) {
+        let prop = &self.raws.props[idx];
+        let mut eb = spawn_base(ecs, &prop.name, &prop.renderable, x, y);
+        if prop.hidden { eb = eb.with(Hidden {}); }
+        if prop.blocks_tile { eb = eb.with(BlocksTile {}); }
+        if prop.blocks_visibility { eb = eb.with(BlocksVisibility {}); }
+        if prop.door { eb = eb.with(Door { open: false }); }
+
+        if let Some(trap) = &prop.trap {
+            eb = eb.with(EntryTrigger {});
+            if let Some(damage) = trap.damage { eb = eb.with(InflictsDamage { damage }); }
+            if let Some(status) = &trap.status { eb = eb.with(causes_status(status)); }
+            if trap.teleport { eb = eb.with(TeleportsVictim {}); }
+            if trap.single_activation { eb = eb.with(SingleActivation {}); }
+        }
+
+        eb.build();
+    }
+}
+
+fn spawn_table(entries: &[SpawnTableEntry], map_depth: i32) -> RandomTable<&str> {
+    let mut table = RandomTable::new();
+    for spawn in entries.iter() {
+        let Some(weight) = spawn.weight_at(map_depth) else { continue };
+        table = if spawn.table.is_empty() {
+            table.add(spawn.name.as_str(), weight)
+        } else {
+            table.add_table(spawn_table(&spawn.table, map_depth), weight)
+        };
+    }
+    table
+}
+
+fn check_spawn_table(entries: &[SpawnTableEntry], used_names: &HashSet<String>) {
+    for spawn in entries.iter() {
+        if spawn.table.is_empty() && !used_names.contains(&spawn.name) {
+            rltk::console::log(format!("WARNING: spawn table entry [{}] has no entity to spawn", spawn.name));
+        }
+        if spawn.max_depth.is_some_and(|max| max < spawn.min_depth) {
+            rltk::console::log(format!("WARNING: spawn table entry [{}] ends before it starts", spawn.name));
+        }
+        check_spawn_table(&spawn.table, used_names);
+    }
+}
+
+/// Maps each name to its position in the list, warning about any name that is already taken.
+fn index_names<'a>(names: impl Iterator<Item = &'a String>, used_names: &mut HashSet<String>) -> HashMap<String, usize> {
+    let mut index = HashMap::new();
+    for (i, name) in names.enumerate() {
+        if !used_names.insert(name.clone()) {
+            rltk::console::log(format!("WARNING: duplicate entity name in raws [{}]", name));
+        }
+        index.insert(name.clone(), i);
+    }
+    index
+}
+
+/// What every spawned entity has: a name, a place, a look and a spot in the save file.
+fn spawn_base<'a>(ecs: &'a mut World, name: &str, renderable: &Option<RawRenderable>, x: i32, y: i32) -> EntityBuilder<'a> {
+    let mut eb = ecs.create_entity()
+        .with(Position { x, y })
+        .with(Name { name: name.to_string() })
+        .marked::<SimpleMarker<SerializeMe>>();
+    if let Some(renderable) = renderable {
+        eb = eb.with(Renderable {
+            glyph: rltk::to_cp437(renderable.glyph.chars().next().unwrap_or('?')),
+            fg: parse_colour(&renderable.fg),
+            bg: parse_colour(&renderable.bg),
+            render_order: renderable.order,
+        });
+    }
+    eb
+}
+
+fn causes_status(status: &RawStatus) -> CausesStatus {
+    CausesStatus { effect: StatusEffect { kind: status.kind, turns: status.turns, magnitude: status.magnitude } }
+}
+
+fn parse_colour(hex: &str) -> RGB {
+    RGB::from_hex(hex).unwrap_or_else(|_| {
+        rltk::console::log(format!("WARNING: [{}] is not a colour", hex));
+        RGB::named(rltk::MAGENTA)
+    })
+}
+
+/// Reads dice notation like `"2d6+1"`, `"1d8"` or `"1d4-1"` into `(n_dice, die_type, bonus)`.
+pub fn parse_dice_string(dice: &str) -> (i32, i32, i32) {
+    let (dice, bonus) = match dice.find(['+', '-']) {
+        Some(sign) => (&dice[..sign], dice[sign..].trim_start_matches('+').parse().unwrap_or(0)),
+        None => (dice, 0),
+    };
+    match dice.split_once('d') {
+        Some((n_dice, die_type)) => (n_dice.parse().unwrap_or(1), die_type.parse().unwrap_or(1), bonus),
+        None => {
+            rltk::console::log(format!("WARNING: [{}] is not dice notation", dice));
+            (1, 1, bonus)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dice_with_and_without_a_bonus() {
+        assert_eq!(parse_dice_string("1d8"), (1, 8, 0));
+        assert_eq!(parse_dice_string("2d6+1"), (2, 6, 1));
+        assert_eq!(parse_dice_string("1d4-1"), (1, 4, -1));
+        assert_eq!(parse_dice_string("3d10+12"), (3, 10, 12));
+    }
+
+    #[test]
+    fn plain_numbers_become_a_flat_bonus() {
+        assert_eq!(parse_dice_string("+3"), (1, 1, 3));
+        assert_eq!(parse_dice_string("sword"), (1, 1, 0));
+    }
+}
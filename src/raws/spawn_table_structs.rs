@@ -0,0 +1,73 @@
+use serde::Deserialize;
+
+/// One line of the spawn table: something that can spawn, or a group of things that can.
+#[derive(Deserialize, Debug)]
+pub struct SpawnTableEntry {
+    pub name: String,
+    /// The shallowest depth the entry can turn up on.
+    #[serde(default = "first_depth")]
+    pub min_depth: i32,
+    /// The deepest depth the entry can turn up on; no limit if left out.
+    #[serde(default)]
+    pub max_depth: Option<i32>,
+    /// The weight is `weight + weight_per_depth * depth`, so a positive `weight_per_depth` makes
+    /// the entry more common the deeper you go and a negative one makes it rarer.
+    pub weight: i32,
+    #[serde(default)]
+    pub weight_per_depth: i32,
+    /// Makes the entry a group ("any scroll") that rolls again on these entries. The group's
+    /// `name` is only a label.
+    #[serde(default)]
+    pub table: Vec<SpawnTableEntry>,
+}
+
+impl SpawnTableEntry {
+    /// The entry's weight at `map_depth`, or `None` if it can't spawn there at all.
+    pub fn weight_at(&self, map_depth: i32) -> Option<i32> {
+        if map_depth < self.min_depth || self.max_depth.is_some_and(|max| map_depth > max) {
+            return None;
+        }
+        let weight = self.weight + self.weight_per_depth * map_depth;
+        if weight > 0 { Some(weight) } else { None }
+    }
+}
+
+fn first_depth() -> i32 { 1 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(json: &str) -> SpawnTableEntry {
+        serde_json::from_str(json).expect("Unable to parse test entry")
+    }
+
+    #[test]
+    fn depth_defaults_to_every_level() {
+        let goblin = entry(r#"{ "name": "Goblin", "weight": 10 }"#);
+        assert_eq!(goblin.min_depth, 1);
+        assert_eq!(goblin.max_depth, None);
+        assert_eq!(goblin.weight_at(1), Some(10));
+        assert_eq!(goblin.weight_at(50), Some(10));
+    }
+
+    #[test]
+    fn entries_outside_their_depths_are_filtered() {
+        let orc = entry(r#"{ "name": "Orc", "min_depth": 3, "max_depth": 5, "weight": 4 }"#);
+        assert_eq!(orc.weight_at(2), None);
+        assert_eq!(orc.weight_at(3), Some(4));
+        assert_eq!(orc.weight_at(5), Some(4));
+        assert_eq!(orc.weight_at(6), None);
+    }
+
+    #[test]
+    fn weight_scales_with_depth_and_drops_out_at_zero() {
+        let rat = entry(r#"{ "name": "Rat", "weight": 10, "weight_per_depth": -2 }"#);
+        assert_eq!(rat.weight_at(1), Some(8));
+        assert_eq!(rat.weight_at(4), Some(2));
+        assert_eq!(rat.weight_at(5), None);
+
+        let ogre = entry(r#"{ "name": "Ogre", "weight": 0, "weight_per_depth": 3 }"#);
+        assert_eq!(ogre.weight_at(2), Some(6));
+    }
+}
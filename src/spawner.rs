@@ -1,5 +1,5 @@
-use std::collections::HashMap;
-use crate::map::Map;
+use std::collections::BTreeMap;
+use crate::map::{Map, TileType};
 use crate::components::*;
 use rltk::{RandomNumberGenerator, RGB};
 use specs::Entity;
@@ -7,8 +7,11 @@ use specs::prelude::*;
 use crate::rect::Rect;
 use specs::saveload::{MarkedBuilder, SimpleMarker};
 use crate::random_table::RandomTable;
+use crate::raws::raws;
 
 const MAX_MONSTERS_PER_ROOM: i32 = 4;
+/// Speed before quickness and equipment are taken into account.
+pub(crate) const BASE_SPEED: i32 = 10;
 
 pub(crate) fn create_player(ecs : &mut World, player_x : i32, player_y : i32) -> Entity {
     ecs
@@ -23,232 +26,86 @@ pub(crate) fn create_player(ecs : &mut World, player_x : i32, player_y : i32) ->
         })
         .with(Viewshed { visible_tiles: Vec::new(), range: 8, dirty: true })
         .with(Name { name: "Player".to_string() })
-        .with(CombatStats { max_hp: 150, hp: 150, defense: 2, power: 5 })
+        .with(CombatStats { max_hp: 150, hp: 150 })
+        .with(Attributes { might: 11, fitness: 11, quickness: 11, intelligence: 11 })
+        .with(Skills { melee: 1, ranged: 1, defense: 1, magic: 1 })
+        .with(HungerClock { state: HungerState::WellFed, duration: 20 })
+        .with(Experience { level: 1, xp: 0 })
+        .with(Initiative { speed: BASE_SPEED, energy: 0 })
+        .with(MyTurn {})
         .marked::<SimpleMarker<SerializeMe>>()
         .build()
 }
 
-#[allow(clippy::map_entry)]
-pub fn spawn_room(ecs: &mut World, room: &Rect, map: &Map, map_depth: i32) {
-    let spawn_table = room_table(map_depth);
-    let mut spawn_points: HashMap<usize, String> = HashMap::new();
-
-    {
-        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
-        let num_spawns = rng.roll_dice(1, MAX_MONSTERS_PER_ROOM + 3) + (map_depth - 1) - 3;
-
-        for _i in 0..num_spawns {
-            let mut added = false;
-            let mut tries = 0;
-            while !added && tries < 20 {
-                let x = (room.x1 + rng.roll_dice(1, i32::abs(room.x2 - room.x1))) as usize;
-                let y = (room.y1 + rng.roll_dice(1, i32::abs(room.y2 - room.y1))) as usize;
-                let idx = (y * map.width as usize) + x;
-                if !spawn_points.contains_key(&idx) {
-                    spawn_points.insert(idx, spawn_table.roll(&mut rng));
-                    added = true;
-                } else {
-                    tries += 1;
-                }
+pub fn spawn_room(map: &Map, rng: &mut RandomNumberGenerator, room: &Rect, map_depth: i32, spawn_list: &mut Vec<(usize, String)>) {
+    let mut possible_targets: Vec<usize> = Vec::new();
+    for y in room.y1 + 1..=room.y2 {
+        for x in room.x1 + 1..=room.x2 {
+            let idx = map.xy_idx(x, y);
+            if map.tiles[idx] == TileType::Floor {
+                possible_targets.push(idx);
             }
         }
     }
 
-    for spawn in spawn_points.iter() {
-        let x = (*spawn.0 % map.width as usize) as i32;
-        let y = (*spawn.0 / map.width as usize) as i32;
-
-        match spawn.1.as_ref() {
-            "Goblin" => goblin(ecs, x, y),
-            "Orc" => orc(ecs, x, y),
-            "Health Potion" => health_potion(ecs, x, y),
-            "Fireball Scroll" => fireball_scroll(ecs, x, y),
-            "Confusion Scroll" => confusion_scroll(ecs, x, y),
-            "Magic Missile Scroll" => magic_missile_scroll(ecs, x, y),
-            "Dagger" => dagger(ecs, x, y),
-            "Shield" => shield(ecs, x, y),
-            "Longsword" => longsword(ecs, x, y),
-            "Tower Shield" => tower_shield(ecs, x, y),
-            _ => {}
-        }
-    }
-}
-
-fn room_table(map_depth: i32) -> RandomTable {
-    RandomTable::new()
-        .add("Goblin", 10)
-        .add("Orc", 1 + map_depth)
-        .add("Health Potion", 7)
-        .add("Fireball Scroll", 2 + map_depth)
-        .add("Confusion Scroll", 2 + map_depth)
-        .add("Magic Missile Scroll", 4)
-        .add("Dagger", 3)
-        .add("Shield", 3)
-        .add("Longsword", map_depth - 1)
-        .add("Tower Shield", map_depth - 1)
-}
-
-fn orc(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('o'), "Orc");
+    spawn_region(rng, &possible_targets, map_depth, spawn_list);
 }
 
-fn goblin(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('g'), "Goblin");
-}
+/// Spawns into maps that have no rooms. Walkable tiles are grouped into Voronoi cells, and each
+/// cell is filled just like a room would be. The cell the player starts in is left empty, the
+/// same way the first room is.
+pub fn spawn_voronoi_regions(map: &Map, rng: &mut RandomNumberGenerator, start_idx: usize, map_depth: i32, spawn_list: &mut Vec<(usize, String)>) {
+    let regions = voronoi_regions(map, rng);
 
-fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: rltk::FontCharType, name: S) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph,
-            fg: RGB::named(rltk::RED),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 1,
-        })
-        .with(Viewshed { visible_tiles: Vec::new(), range: 8, dirty: true })
-        .with(Monster {})
-        .with(Name { name: name.to_string() })
-        .with(BlocksTile {})
-        .with(CombatStats { max_hp: 16, hp: 16, defense: 1, power: 4 })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
-}
-
-fn health_potion(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph: rltk::to_cp437('¡'),
-            fg: RGB::named(rltk::MAGENTA),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name { name: "Health Potion".to_string() })
-        .with(Item {})
-        .with(Consumable {})
-        .with(ProvidesHealing { heal_amount: 8 })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
-}
-
-fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph: rltk::to_cp437(')'),
-            fg: RGB::named(rltk::CYAN),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name { name: "Magic Missile Scroll".to_string() })
-        .with(Item {})
-        .with(Consumable {})
-        .with(Ranged { range: 6 })
-        .with(InflictsDamage { damage: 8 })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+    for area in regions.values() {
+        if !area.contains(&start_idx) {
+            spawn_region(rng, area, map_depth, spawn_list);
+        }
+    }
 }
 
-fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph: rltk::to_cp437(')'),
-            fg: RGB::named(rltk::ORANGE),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name { name: "Fireball Scroll".to_string() })
-        .with(Item {})
-        .with(Consumable {})
-        .with(Ranged { range: 6 })
-        .with(InflictsDamage { damage: 20 })
-        .with(AreaOfEffect { radius: 3 })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
-}
+fn voronoi_regions(map: &Map, rng: &mut RandomNumberGenerator) -> BTreeMap<i32, Vec<usize>> {
+    let mut noise = rltk::FastNoise::seeded(rng.roll_dice(1, 65536) as u64);
+    noise.set_noise_type(rltk::NoiseType::Cellular);
+    noise.set_frequency(0.08);
+    noise.set_cellular_distance_function(rltk::CellularDistanceFunction::Manhattan);
+
+    // Ordered, so regions are always rolled in the same order for a given seed
+    let mut regions: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            let idx = map.xy_idx(x, y);
+            if map.tiles[idx] == TileType::Floor {
+                // Every tile in a cell gets the same noise value, so it works as the region key
+                let cell_value = (noise.get_noise(x as f32, y as f32) * 10240.0) as i32;
+                regions.entry(cell_value).or_default().push(idx);
+            }
+        }
+    }
 
-fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph: rltk::to_cp437(')'),
-            fg: RGB::named(rltk::PINK),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name { name: "Confusion Scroll".to_string() })
-        .with(Item {})
-        .with(Consumable {})
-        .with(Ranged { range: 6 })
-        .with(Confusion { turns: 4 })
-        .build();
+    regions
 }
 
-fn dagger(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph: rltk::to_cp437('/'),
-            fg: RGB::named(rltk::CYAN),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name { name: "Dagger".to_string() })
-        .with(Item {})
-        .marked::<SimpleMarker<SerializeMe>>()
-        .with(Equippable { slot: EquipmentSlot::Melee })
-        .with(MeleePowerBonus { power: 2 })
-        .build();
-}
+/// Fills an arbitrary set of tiles with rolls from the depth's spawn table. Builders without
+/// rooms hand their own regions in here.
+pub fn spawn_region(rng: &mut RandomNumberGenerator, area: &[usize], map_depth: i32, spawn_list: &mut Vec<(usize, String)>) {
+    let spawn_table = raws().room_table(map_depth);
+    let tiles = area.iter().fold(RandomTable::new(), |tiles, map_idx| tiles.add(*map_idx, 1));
 
-fn shield(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph: rltk::to_cp437('('),
-            fg: RGB::named(rltk::CYAN),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name { name: "Shield".to_string() })
-        .with(Item {})
-        .marked::<SimpleMarker<SerializeMe>>()
-        .with(Equippable { slot: EquipmentSlot::Shield })
-        .with(DefenseBonus { defense: 1 })
-        .build();
+    let num_spawns = i32::min(area.len() as i32, rng.roll_dice(1, MAX_MONSTERS_PER_ROOM + 3) + (map_depth - 1) - 3);
+    for map_idx in tiles.roll_many(rng, usize::try_from(num_spawns).unwrap_or(0)) {
+        if let Some(name) = spawn_table.roll(rng) {
+            spawn_list.push((*map_idx, name.to_string()));
+        }
+    }
 }
 
-fn longsword(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph: rltk::to_cp437('/'),
-            fg: RGB::named(rltk::YELLOW),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name { name: "Longsword".to_string() })
-        .with(Item {})
-        .with(Equippable { slot: EquipmentSlot::Melee })
-        .with(MeleePowerBonus { power: 4 })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
-}
+/// Creates a single named entity at a map index, from its template in the raws.
+pub fn spawn_entity(ecs: &mut World, spawn: &(usize, String)) {
+    let (x, y) = {
+        let map = ecs.fetch::<Map>();
+        ((spawn.0 % map.width as usize) as i32, (spawn.0 / map.width as usize) as i32)
+    };
 
-fn tower_shield(ecs: &mut World, x: i32, y: i32) {
-    ecs.create_entity()
-        .with(Position { x, y })
-        .with(Renderable {
-            glyph: rltk::to_cp437('('),
-            fg: RGB::named(rltk::YELLOW),
-            bg: RGB::named(rltk::BLACK),
-            render_order: 2,
-        })
-        .with(Name { name: "Tower Shield".to_string() })
-        .with(Item {})
-        .with(Equippable { slot: EquipmentSlot::Shield })
-        .with(DefenseBonus { defense: 3 })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+    raws().spawn_named_entity(ecs, &spawn.1, x, y);
 }
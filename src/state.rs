@@ -1,36 +1,63 @@
-use crate::{game_log, gui, map, player, spawner, systems};
-use crate::components::{Position, Ranged, Renderable, WantsToUseItem, WantsToDropItem, Player, InBackpack, Viewshed, CombatStats, Equipped, WantsToRemoveItem};
-use crate::map::Map;
-use crate::menu::main_menu;
+use crate::{camera, dungeon, game_log, gui, map_builders, player, spawner, systems};
+use crate::components::{AreaOfEffect, MyTurn, Position, Ranged, WantsToUseItem, WantsToDropItem, Viewshed, CombatStats, WantsToRemoveItem, WantsToShoot};
+use crate::dungeon::MasterDungeonMap;
+use crate::map::{Map, TileType, MAP_HEIGHT, MAP_WIDTH};
+use crate::game_seed::GameSeed;
+use crate::menu::{main_menu, seed_menu};
 use crate::systems::damage::DamageSystem;
+use crate::systems::hunger::HungerSystem;
+use crate::systems::initiative::InitiativeSystem;
 use crate::systems::inventory::{ItemCollectionSystem, ItemDropSystem, ItemRemoveSystem, ItemUseSystem};
 use crate::systems::map_indexing::MapIndexingSystem;
 use crate::systems::melee_combat::MeleeCombatSystem;
 use crate::systems::monster_ai::MonsterAI;
+use crate::systems::ranged_combat::RangedCombatSystem;
+use crate::systems::status_effect::StatusEffectSystem;
+use crate::systems::trigger::TriggerSystem;
 use crate::visibility_system::VisibilitySystem;
 
-use rltk::{GameState, Point, Rltk};
+use rltk::{GameState, Point, RandomNumberGenerator, Rltk};
 use specs::prelude::*;
 use crate::systems::particle;
 
+/// How long each map generation snapshot stays on screen, in milliseconds.
+const MAPGEN_FRAME_TIME: f32 = 200.0;
+
 pub struct State {
     pub ecs: World,
+    pub mapgen_next_state: Option<RunState>,
+    pub mapgen_history: Vec<Map>,
+    pub mapgen_index: usize,
+    pub mapgen_timer: f32,
 }
 
 impl State {
     fn run_systems(&mut self) {
-        let mut vis = VisibilitySystem {};
-        vis.run_now(&self.ecs);
-
         let mut mob = MonsterAI {};
         mob.run_now(&self.ecs);
 
+        // Visibility reads the door index, so index first or a fresh level starts out see-through
         let mut map_index = MapIndexingSystem {};
         map_index.run_now(&self.ecs);
 
+        let mut triggers = TriggerSystem {};
+        triggers.run_now(&self.ecs);
+
+        let mut vis = VisibilitySystem {};
+        vis.run_now(&self.ecs);
+
         let mut melee = MeleeCombatSystem {};
         melee.run_now(&self.ecs);
 
+        let mut ranged = RangedCombatSystem {};
+        ranged.run_now(&self.ecs);
+
+        let mut hunger = HungerSystem {};
+        hunger.run_now(&self.ecs);
+
+        let mut status_effects = StatusEffectSystem {};
+        status_effects.run_now(&self.ecs);
+
         let mut damage = DamageSystem {};
         damage.run_now(&self.ecs);
         systems::damage::delete_the_dead(&mut self.ecs);
@@ -53,78 +80,67 @@ impl State {
         self.ecs.maintain();
     }
 
-    fn entities_to_remove_on_level_change(&mut self) -> Vec<Entity> {
-        let entities = self.ecs.entities();
-        let player = self.ecs.read_storage::<Player>();
-        let backpack = self.ecs.read_storage::<InBackpack>();
-        let player_entity = self.ecs.fetch::<Entity>();
-        let equipped = self.ecs.read_storage::<Equipped>();
-
-        let mut to_delete: Vec<Entity> = Vec::new();
-        for entity in entities.join() {
-            let mut should_delete = true;
-
-            // Don't delete the player
-            let p = player.get(entity);
-            if let Some(_p) = p {
-                should_delete = false;
-            }
-
-            // Don't delete the player's equipment
-            let bp = backpack.get(entity);
-            if let Some(bp) = bp {
-                if bp.owner == *player_entity {
-                    should_delete = false;
-                }
-            }
-
-            let eq = equipped.get(entity);
-            if let Some(eq) = eq {
-                if eq.owner == *player_entity {
-                    should_delete = false;
-                }
-            }
+    pub fn generate_world_map(&mut self, new_depth: i32) {
+        let mut builder;
+        {
+            let mut rng = self.ecs.write_resource::<RandomNumberGenerator>();
+            builder = map_builders::level_builder(new_depth, &mut rng);
+            builder.build_map(&mut rng);
+        }
+        self.mapgen_history = builder.get_snapshot_history();
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
+        {
+            let mut world_map_resource = self.ecs.write_resource::<Map>();
+            *world_map_resource = builder.get_map();
+        }
 
-            if should_delete {
-                to_delete.push(entity);
-            }
+        // Spawn bad guys
+        builder.spawn_entities(&mut self.ecs);
+
+        // Every level below the first is entered by stairs, so leave a way back up where we arrive
+        let player_start = builder.get_starting_position();
+        if new_depth > 1 {
+            let mut map = self.ecs.write_resource::<Map>();
+            let start_idx = map.xy_idx(player_start.x, player_start.y);
+            map.tiles[start_idx] = TileType::UpStairs;
         }
 
-        to_delete
+        self.place_player(player_start.x, player_start.y);
     }
 
-    fn goto_next_level(&mut self) {
-        // Delete entities that aren't the player or his/her equipment
-        let to_delete = self.entities_to_remove_on_level_change();
-        for target in to_delete {
-            self.ecs.delete_entity(target).expect("Unable to delete entity");
-        }
+    /// Puts a level the player has been to before back in place, with everything on it as it was
+    /// left. The player arrives on the stairs leading back to where they came from.
+    fn restore_world_map(&mut self, mut map: Map, arrived_from_above: bool) {
+        let arrival_tile = if arrived_from_above { TileType::UpStairs } else { TileType::DownStairs };
+        let arrival_idx = map.tiles.iter().position(|tile| *tile == arrival_tile).expect("Level has no stairs to arrive on");
+        let arrival_x = arrival_idx as i32 % map.width;
+        let arrival_y = arrival_idx as i32 / map.width;
 
-        // Build a new map and place the player
-        let world_map;
-        let current_depth;
+        map.tile_content = vec![Vec::new(); map.tiles.len()];
         {
             let mut world_map_resource = self.ecs.write_resource::<Map>();
-            current_depth = world_map_resource.depth;
-            *world_map_resource = Map::new_with_rooms_and_corridors(current_depth + 1);
-            world_map = world_map_resource.clone();
+            *world_map_resource = map;
         }
+        dungeon::thaw_level_entities(&mut self.ecs);
 
-        // Spawn bad guys
-        for room in world_map.rooms.iter().skip(1) {
-            spawner::spawn_room(&mut self.ecs, room, &world_map, current_depth);
-        }
+        // Nothing was generated, so there is nothing for the visualizer to show
+        self.mapgen_history.clear();
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
 
-        // Place the player and update resources
-        let (player_x, player_y) = world_map.rooms[0].center();
+        self.place_player(arrival_x, arrival_y);
+    }
+
+    fn place_player(&mut self, x: i32, y: i32) {
         let mut player_position = self.ecs.write_resource::<Point>();
-        *player_position = Point::new(player_x, player_y);
+        *player_position = Point::new(x, y);
         let mut position_components = self.ecs.write_storage::<Position>();
         let player_entity = self.ecs.fetch::<Entity>();
         let player_pos_comp = position_components.get_mut(*player_entity);
         if let Some(player_pos_comp) = player_pos_comp {
-            player_pos_comp.x = player_x;
-            player_pos_comp.y = player_y;
+            player_pos_comp.x = x;
+            player_pos_comp.y = y;
         }
 
         // Mark the player's visibility as dirty
@@ -133,8 +149,33 @@ impl State {
         if let Some(vs) = vs {
             vs.dirty = true;
         }
+    }
+
+    /// Takes the stairs `offset` levels down (or up, if negative). The level being left is stored
+    /// along with everything on it; the destination is restored if it has been visited before and
+    /// generated otherwise.
+    fn goto_level(&mut self, offset: i32) {
+        dungeon::freeze_level_entities(&mut self.ecs);
+
+        let current_depth = self.ecs.fetch::<Map>().depth;
+        {
+            let map = self.ecs.fetch::<Map>();
+            let mut dungeon_master = self.ecs.write_resource::<MasterDungeonMap>();
+            dungeon_master.store_map(&map);
+        }
+
+        let new_depth = current_depth + offset;
+        let stored_map = self.ecs.fetch::<MasterDungeonMap>().get_map(new_depth);
+        if let Some(map) = stored_map {
+            self.restore_world_map(map, offset > 0);
+            let message = if offset > 0 { "You descend to the level below." } else { "You climb back up to the level above." };
+            self.ecs.fetch_mut::<game_log::GameLog>().entries.push(message.to_string());
+            return;
+        }
 
-        // Notify the player and give them some health
+        // Reaching a level for the first time gives the player a moment to heal
+        self.generate_world_map(new_depth);
+        let player_entity = self.ecs.fetch::<Entity>();
         let mut game_log = self.ecs.fetch_mut::<game_log::GameLog>();
         game_log.entries.push("You descend to the next level, and take a moment to heal.".to_string());
         let mut player_health_store = self.ecs.write_storage::<CombatStats>();
@@ -144,49 +185,32 @@ impl State {
         }
     }
 
-    fn game_over_cleanup(&mut self) {
-        // Delete everything
-        let mut to_delete = Vec::new();
-        for e in self.ecs.entities().join() {
-            to_delete.push(e);
-        }
-        for del in to_delete.iter() {
-            self.ecs.delete_entity(*del).expect("Deletion failed");
-        }
-
-        // Build a new map and place the player
-        let world_map;
-        {
-            let mut world_map_resource = self.ecs.write_resource::<Map>();
-            *world_map_resource = Map::new_with_rooms_and_corridors(1);
-            world_map = world_map_resource.clone();
-        }
-
-        // Spawn bad guys
-        for room in world_map.rooms.iter().skip(1) {
-            spawner::spawn_room(&mut self.ecs, room, &world_map, 1);
-        }
-
-        // Place the player and update resources
-        let (player_x, player_y) = world_map.rooms[0].center();
-        let player_entity = spawner::create_player(&mut self.ecs, player_x, player_y);
-        let mut player_position = self.ecs.write_resource::<Point>();
-        *player_position = Point::new(player_x, player_y);
-        let mut position_components = self.ecs.write_storage::<Position>();
-        let mut player_entity_writer = self.ecs.write_resource::<Entity>();
-        *player_entity_writer = player_entity;
-        let player_pos_comp = position_components.get_mut(player_entity);
-        if let Some(player_pos_comp) = player_pos_comp {
-            player_pos_comp.x = player_x;
-            player_pos_comp.y = player_y;
-        }
+    /// Throws away the current run and starts a fresh one from `seed`.
+    pub fn new_game(&mut self, seed: u64) {
+        // Start over from an empty world, so the run depends on nothing but the seed
+        self.ecs = crate::create_world();
+        self.ecs.insert(Map::new(1, MAP_WIDTH, MAP_HEIGHT));
+        self.ecs.insert(RandomNumberGenerator::seeded(seed));
+        self.ecs.insert(GameSeed { seed });
+        self.ecs.insert(MasterDungeonMap::new());
+        self.ecs.insert(game_log::GameLog { entries: vec!["Welcome to Rusty Roguelike".to_string()] });
+
+        // Spawn a new player and build the first level around them
+        let player_entity = spawner::create_player(&mut self.ecs, 0, 0);
+        self.ecs.insert(player_entity);
+        self.generate_world_map(1);
+    }
+}
 
-        // Mark the player's visibility as dirty
-        let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
-        let vs = viewshed_components.get_mut(player_entity);
-        if let Some(vs) = vs {
-            vs.dirty = true;
-        }
+/// Takes the turn away from every monster that just had one. The player keeps theirs until they
+/// have chosen what to do with it.
+fn end_monster_turns(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let mut turns = ecs.write_storage::<MyTurn>();
+    let entities = ecs.entities();
+    let finished: Vec<Entity> = (&entities, &turns).join().map(|(entity, _turn)| entity).filter(|entity| *entity != player_entity).collect();
+    for entity in finished {
+        turns.remove(entity);
     }
 }
 
@@ -202,44 +226,68 @@ impl GameState for State {
         particle::cull_dead_particles(&mut self.ecs, ctx);
 
         match new_run_state {
-            RunState::MainMenu { .. } => {}
+            RunState::MainMenu { .. } | RunState::EnterSeed { .. } | RunState::MapGeneration => {}
             _ => {
-                map::draw_map(&self.ecs, ctx);
-                {
-                    let positions = self.ecs.read_storage::<Position>();
-                    let renderables = self.ecs.read_storage::<Renderable>();
-                    let map = self.ecs.fetch::<Map>();
-
-                    let mut data = (&positions, &renderables).join().collect::<Vec<_>>();
-                    data.sort_by(|&a, &b| b.1.render_order.cmp(&a.1.render_order));
-                    for (pos, render) in data.iter() {
-                        let idx = map.xy_idx(pos.x, pos.y);
-                        if map.visible_tiles[idx] { ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph) }
-                    }
-
-                    gui::draw_ui(&self.ecs, ctx);
-                }
+                camera::render_camera(&self.ecs, ctx);
+                gui::draw_ui(&self.ecs, ctx);
             }
         }
 
         match new_run_state {
+            RunState::MapGeneration => {
+                if !map_builders::show_mapgen_visualizer() || self.mapgen_index >= self.mapgen_history.len() {
+                    new_run_state = self.mapgen_next_state.unwrap();
+                } else {
+                    camera::render_debug_map(&self.mapgen_history[self.mapgen_index], ctx);
+
+                    self.mapgen_timer += ctx.frame_time_ms;
+                    if self.mapgen_timer > MAPGEN_FRAME_TIME {
+                        self.mapgen_timer = 0.0;
+                        self.mapgen_index += 1;
+                    }
+                }
+            }
             RunState::PreRun => {
                 self.run_systems();
                 self.ecs.maintain();
                 new_run_state = RunState::AwaitingInput;
             }
             RunState::AwaitingInput => {
-                new_run_state = player::read_input(self, ctx);
+                // A level earned mid-turn is taken once the monsters have had their go
+                if player::can_level_up(&self.ecs) {
+                    new_run_state = RunState::LevelUp;
+                } else if !player::has_initiative(&self.ecs) {
+                    new_run_state = RunState::Ticking;
+                } else if player::loses_turn(&mut self.ecs) {
+                    new_run_state = RunState::PlayerTurn;
+                } else {
+                    new_run_state = player::read_input(self, ctx);
+                }
             }
             RunState::PlayerTurn => {
                 self.run_systems();
                 self.ecs.maintain();
-                new_run_state = RunState::MonsterTurn;
+                player::end_turn(&mut self.ecs);
+                new_run_state = RunState::Ticking;
             }
-            RunState::MonsterTurn => {
-                self.run_systems();
-                self.ecs.maintain();
-                new_run_state = RunState::AwaitingInput;
+            RunState::Ticking => {
+                // Play out everyone else's turns until the player's comes up, or they die waiting
+                loop {
+                    let mut initiative = InitiativeSystem {};
+                    initiative.run_now(&self.ecs);
+                    if player::has_initiative(&self.ecs) {
+                        new_run_state = RunState::AwaitingInput;
+                    }
+
+                    self.run_systems();
+                    self.ecs.maintain();
+                    end_monster_turns(&mut self.ecs);
+
+                    if *self.ecs.fetch::<RunState>() == RunState::GameOver {
+                        new_run_state = RunState::GameOver;
+                    }
+                    if new_run_state != RunState::Ticking { break; }
+                }
             }
             RunState::ShowInventory => {
                 let result = gui::show_inventory(self, ctx);
@@ -278,7 +326,8 @@ impl GameState for State {
                 }
             }
             RunState::ShowTargeting { range, item } => {
-                let result = gui::ranged_target(self, ctx, range);
+                let radius = self.ecs.read_storage::<AreaOfEffect>().get(item).map(|aoe| aoe.radius);
+                let result = gui::ranged_target(self, ctx, range, radius);
                 match result.0 {
                     gui::ItemMenuResult::Cancel => new_run_state = RunState::AwaitingInput,
                     gui::ItemMenuResult::NoResponse => {}
@@ -291,13 +340,29 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::ShowFireTargeting { range } => {
+                let result = gui::ranged_target(self, ctx, range, None);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => new_run_state = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let mut intent = self.ecs.write_storage::<WantsToShoot>();
+                        intent
+                            .insert(*self.ecs.fetch::<Entity>(), WantsToShoot { target: result.1.unwrap() })
+                            .expect("Unable to insert intent");
+                        new_run_state = RunState::PlayerTurn;
+                    }
+                }
+            }
             RunState::MainMenu { .. } => {
                 let result = main_menu(self, ctx);
                 match result {
                     gui::MainMenuResult::NoSelection { selected } => new_run_state = RunState::MainMenu { menu_selection: selected },
                     gui::MainMenuResult::Selected { selected } => {
                         match selected {
-                            gui::MainMenuSelection::NewGame => new_run_state = RunState::PreRun,
+                            gui::MainMenuSelection::NewGame => {
+                                new_run_state = RunState::EnterSeed { seed: RandomNumberGenerator::new().next_u64() };
+                            }
                             gui::MainMenuSelection::LoadGame => {
                                 systems::save_load::load_game(&mut self.ecs);
                                 new_run_state = RunState::AwaitingInput;
@@ -308,13 +373,31 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::EnterSeed { seed } => {
+                let result = seed_menu(ctx, seed);
+                match result {
+                    gui::SeedMenuResult::NoSelection { seed } => new_run_state = RunState::EnterSeed { seed },
+                    gui::SeedMenuResult::Cancel => new_run_state = RunState::MainMenu { menu_selection: gui::MainMenuSelection::NewGame },
+                    gui::SeedMenuResult::Selected { seed } => {
+                        self.new_game(seed);
+                        self.mapgen_next_state = Some(RunState::PreRun);
+                        new_run_state = RunState::MapGeneration;
+                    }
+                }
+            }
             RunState::SaveGame => {
                 systems::save_load::save_game(&mut self.ecs);
                 new_run_state = RunState::MainMenu { menu_selection: gui::MainMenuSelection::LoadGame };
             }
             RunState::NextLevel => {
-                self.goto_next_level();
-                new_run_state = RunState::PreRun;
+                self.goto_level(1);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                new_run_state = RunState::MapGeneration;
+            }
+            RunState::PreviousLevel => {
+                self.goto_level(-1);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                new_run_state = RunState::MapGeneration;
             }
             RunState::ShowRemoveItem => {
                 let result = gui::remove_item_menu(self, ctx);
@@ -329,12 +412,21 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::LevelUp => {
+                let result = gui::level_up_menu(ctx);
+                match result {
+                    gui::LevelUpResult::NoSelection => {}
+                    gui::LevelUpResult::Selected { choice } => {
+                        player::level_up(&mut self.ecs, choice);
+                        new_run_state = RunState::AwaitingInput;
+                    }
+                }
+            }
             RunState::GameOver => {
                 let result = gui::game_over(ctx);
                 match result {
                     gui::GameOverResult::NoSelection => {}
                     gui::GameOverResult::QuitToMenu => {
-                        self.game_over_cleanup();
                         new_run_state = RunState::MainMenu { menu_selection: gui::MainMenuSelection::NewGame };
                     }
                 }
@@ -354,15 +446,23 @@ impl GameState for State {
 #[derive(PartialEq, Copy, Clone)]
 pub enum RunState {
     AwaitingInput,
-    MonsterTurn,
+    /// The player has chosen what to do; play it out.
     PlayerTurn,
+    /// Running the clock and everyone else's turns until the player has initiative again.
+    Ticking,
     PreRun,
     ShowDropItem,
     ShowInventory,
     ShowTargeting { range: i32, item: Entity },
+    /// Aiming the equipped ranged weapon.
+    ShowFireTargeting { range: i32 },
     MainMenu { menu_selection: gui::MainMenuSelection },
+    EnterSeed { seed: u64 },
+    MapGeneration,
     SaveGame,
     NextLevel,
+    PreviousLevel,
     ShowRemoveItem,
+    LevelUp,
     GameOver,
 }
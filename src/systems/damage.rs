@@ -1,4 +1,4 @@
-use crate::components::{CombatStats, Name, Player, Position, SufferDamage};
+use crate::components::{CombatStats, Experience, GrantsExperience, Name, Player, Position, SufferDamage};
 use crate::game_log::GameLog;
 use specs::prelude::*;
 use crate::map::Map;
@@ -7,19 +7,35 @@ use crate::state::RunState;
 pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteStorage<'a, CombatStats>,
         WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, Position>,
         WriteExpect<'a, Map>,
         Entities<'a>,
+        WriteStorage<'a, Experience>,
+        ReadStorage<'a, GrantsExperience>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut stats, mut damage, positions, mut map, entities) = data;
+        let (mut stats, mut damage, positions, mut map, entities, mut experience, grants_experience,
+            player_entity, mut log) = data;
+
+        let mut kills: Vec<(Entity, Entity)> = Vec::new();
+        for (entity, stats, damage) in (&entities, &mut stats, &damage).join() {
+            for (amount, attacker) in damage.amount.iter() {
+                let was_alive = stats.hp > 0;
+                stats.hp -= amount;
+                if was_alive && stats.hp < 1 {
+                    if let Some(attacker) = attacker {
+                        kills.push((*attacker, entity));
+                    }
+                }
+            }
 
-        for (entity, mut stats, damage) in (&entities, &mut stats, &damage).join() {
-            stats.hp -= damage.amount.iter().sum::<i32>();
             let pos = positions.get(entity);
             if let Some(pos) = pos {
                 let idx = map.xy_idx(pos.x, pos.y);
@@ -28,6 +44,16 @@ impl<'a> System<'a> for DamageSystem {
         }
 
         damage.clear();
+
+        // Whoever landed the killing blow gets the credit
+        for (killer, victim) in kills {
+            if let (Some(exp), Some(grants)) = (experience.get_mut(killer), grants_experience.get(victim)) {
+                exp.xp += grants.xp;
+                if killer == *player_entity {
+                    log.entries.push(format!("You gain {} experience.", grants.xp));
+                }
+            }
+        }
     }
 }
 
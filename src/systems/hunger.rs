@@ -0,0 +1,61 @@
+use specs::prelude::*;
+use crate::components::{HungerClock, HungerState, MyTurn, SufferDamage};
+use crate::game_log::GameLog;
+use crate::state::RunState;
+use crate::systems::initiative::is_taking_turn;
+
+/// How many turns each hunger state lasts before getting worse.
+const HUNGER_STATE_DURATION: i32 = 200;
+const STARVATION_DAMAGE: i32 = 1;
+
+/// Ticks hunger clocks once per turn of whoever has them, and makes the starving suffer for it.
+pub struct HungerSystem {}
+
+impl<'a> System<'a> for HungerSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, HungerClock>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteStorage<'a, SufferDamage>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, MyTurn>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut hunger_clock, player_entity, run_state, mut suffer_damage, mut log, turns) = data;
+
+        for (entity, clock) in (&entities, &mut hunger_clock).join() {
+            if !is_taking_turn(entity, *player_entity, *run_state, &turns) { continue; }
+            let is_player = entity == *player_entity;
+
+            // Starving never wears off on its own; it just keeps hurting until something is eaten
+            if clock.state == HungerState::Starving {
+                if is_player {
+                    log.entries.push(format!("Your hunger pangs are getting painful! You suffer {} hp damage.", STARVATION_DAMAGE));
+                }
+                SufferDamage::new_damage(&mut suffer_damage, entity, STARVATION_DAMAGE, None);
+                continue;
+            }
+
+            clock.duration -= 1;
+            if clock.duration > 0 { continue; }
+
+            clock.duration = HUNGER_STATE_DURATION;
+            match clock.state {
+                HungerState::WellFed => {
+                    clock.state = HungerState::Normal;
+                    if is_player { log.entries.push("You are no longer well fed.".to_string()); }
+                }
+                HungerState::Normal => {
+                    clock.state = HungerState::Hungry;
+                    if is_player { log.entries.push("You are hungry.".to_string()); }
+                }
+                HungerState::Hungry | HungerState::Starving => {
+                    clock.state = HungerState::Starving;
+                    if is_player { log.entries.push("You are starving!".to_string()); }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use specs::prelude::*;
+use crate::components::{Attributes, Equipped, Initiative, MyTurn, Position, SpeedPenalty, StatusEffects};
+use crate::state::RunState;
+
+/// The energy an action costs. At speed 10 something acts once every 10 ticks of the clock.
+pub const ACTION_COST: i32 = 100;
+
+/// Runs the energy clock forward until something on the level is ready to act, and gives
+/// everything that is its turn.
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Initiative>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, SpeedPenalty>,
+        ReadStorage<'a, Attributes>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut initiatives, positions, mut turns, statuses, equipped, speed_penalties, attributes) = data;
+
+        // Someone is still waiting to take the turn they were given
+        if turns.join().next().is_some() { return; }
+
+        // Only things on this level take turns; those left behind on other levels have no Position
+        let mut actors: Vec<(Entity, i32)> = Vec::new();
+        for (entity, initiative, _pos) in (&entities, &initiatives, &positions).join() {
+            let mut speed = initiative.speed + 2 * attributes.get(entity).map_or(0, |attr| Attributes::bonus(attr.quickness));
+            for (penalty, equipped_by) in (&speed_penalties, &equipped).join() {
+                if equipped_by.owner == entity {
+                    speed -= penalty.penalty;
+                }
+            }
+            if let Some(status) = statuses.get(entity) {
+                speed = status.modify_speed(speed);
+            }
+            actors.push((entity, i32::max(1, speed)));
+        }
+        if actors.is_empty() { return; }
+
+        loop {
+            let mut anyone_ready = false;
+            for (entity, speed) in actors.iter() {
+                let initiative = initiatives.get_mut(*entity).unwrap();
+                initiative.energy += speed;
+                if initiative.energy >= ACTION_COST {
+                    initiative.energy -= ACTION_COST;
+                    turns.insert(*entity, MyTurn {}).expect("Unable to insert turn");
+                    anyone_ready = true;
+                }
+            }
+            if anyone_ready { return; }
+        }
+    }
+}
+
+/// True if `entity`'s turn is being played out by the systems running now. The player's turn is
+/// played out in `RunState::PlayerTurn`, once their action is chosen; everyone else's while the
+/// clock is `RunState::Ticking`.
+pub fn is_taking_turn(entity: Entity, player_entity: Entity, run_state: RunState, turns: &ReadStorage<MyTurn>) -> bool {
+    let players_turn = match run_state {
+        RunState::PlayerTurn => true,
+        RunState::Ticking => false,
+        _ => return false,
+    };
+    turns.get(entity).is_some() && (entity == player_entity) == players_turn
+}
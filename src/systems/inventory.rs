@@ -3,6 +3,7 @@ use crate::components::*;
 use crate::game_log::GameLog;
 use crate::map::Map;
 use crate::systems::particle::ParticleBuilder;
+use crate::systems::status_effect::status_particle;
 
 pub struct ItemCollectionSystem {}
 
@@ -11,22 +12,36 @@ impl<'a> System<'a> for ItemCollectionSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
+        Entities<'a>,
         WriteStorage<'a, WantsToPickupItem>,
         WriteStorage<'a, Position>,
         ReadStorage<'a, Name>,
         WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, Ammunition>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut game_log, mut wants_pickup, mut positions, names, mut backpack) = data;
+        let (player_entity, mut game_log, entities, mut wants_pickup, mut positions, names, mut backpack, mut ammunition) = data;
 
         for pickup in wants_pickup.join() {
-            positions.remove(pickup.item);
-            backpack.insert(pickup.item, InBackpack { owner: pickup.collected_by }).expect("Unable to insert backpack entry");
-
             if pickup.collected_by == *player_entity {
                 game_log.entries.push(format!("You pick up the {}.", names.get(pickup.item).unwrap().name));
             }
+
+            // Ammunition goes on top of a stack of the same kind, if there's one in the backpack
+            if let Some(ammo) = ammunition.get(pickup.item).cloned() {
+                let stack = (&entities, &ammunition, &backpack).join()
+                    .find(|(stack, stack_ammo, pack)| pack.owner == pickup.collected_by && stack_ammo.kind == ammo.kind && *stack != pickup.item)
+                    .map(|(stack, _stack_ammo, _pack)| stack);
+                if let Some(stack) = stack {
+                    ammunition.get_mut(stack).unwrap().count += ammo.count;
+                    entities.delete(pickup.item).expect("Delete failed");
+                    continue;
+                }
+            }
+
+            positions.remove(pickup.item);
+            backpack.insert(pickup.item, InBackpack { owner: pickup.collected_by }).expect("Unable to insert backpack entry");
         }
 
         wants_pickup.clear();
@@ -50,19 +65,25 @@ impl<'a> System<'a> for ItemUseSystem {
         WriteStorage<'a, CombatStats>,
         WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, AreaOfEffect>,
-        WriteStorage<'a, Confusion>,
+        ReadStorage<'a, CausesStatus>,
+        WriteStorage<'a, StatusEffects>,
         ReadStorage<'a, Equippable>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
         WriteExpect<'a, ParticleBuilder>,
         ReadStorage<'a, Position>,
+        ReadStorage<'a, ProvidesFood>,
+        WriteStorage<'a, HungerClock>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
     );
 
     #[allow(clippy::cognitive_complexity)]
     fn run(&mut self, data: Self::SystemData) {
         let (player_entity, mut game_log, map, entities, mut wants_use, names,
             consumables, healing, inflict_damage, mut combat_stats, mut suffer_damage,
-            aoe, confused, equippable, mut equipped, mut backpack, mut particle_builder, positions) = data;
+            aoe, causes_status, mut statuses, equippable, mut equipped, mut backpack, mut particle_builder, positions,
+            provides_food, mut hunger_clocks, attributes, skills) = data;
 
         for (entity, use_item) in (&entities, &wants_use).join() {
             let mut used_item = true;
@@ -148,17 +169,39 @@ impl<'a> System<'a> for ItemUseSystem {
                 }
             }
 
+            let item_edible = provides_food.get(use_item.item);
+            match item_edible {
+                None => {}
+                Some(food) => {
+                    used_item = false;
+                    for target in targets.iter() {
+                        if let Some(hunger) = hunger_clocks.get_mut(*target) {
+                            hunger.state = HungerState::WellFed;
+                            hunger.duration = food.turns;
+                            if entity == *player_entity {
+                                game_log.entries.push(format!("You eat the {}.", names.get(use_item.item).unwrap().name));
+                            }
+                            used_item = true;
+                        }
+                    }
+                }
+            }
+
             let item_damages = inflict_damage.get(use_item.item);
             match item_damages {
                 None => {}
                 Some(damage) => {
                     used_item = false;
+                    // The user's grasp of magic makes the item hit harder (or, for the dim, softer)
+                    let magic_bonus = attributes.get(entity).map_or(0, |attr| Attributes::bonus(attr.intelligence))
+                        + skills.get(entity).map_or(0, |skill| skill.magic);
+                    let amount = i32::max(1, damage.damage + magic_bonus);
                     for mob in targets.iter() {
-                        SufferDamage::new_damage(&mut suffer_damage, *mob, damage.damage);
+                        SufferDamage::new_damage(&mut suffer_damage, *mob, amount, Some(entity));
                         if entity == *player_entity {
                             let mob_name = names.get(*mob).unwrap();
                             let item_name = names.get(use_item.item).unwrap();
-                            game_log.entries.push(format!("You use {} on {}, inflicting {} hp.", item_name.name, mob_name.name, damage.damage));
+                            game_log.entries.push(format!("You use {} on {}, inflicting {} hp.", item_name.name, mob_name.name, amount));
 
                             let pos = positions.get(*mob);
                             if let Some(pos) = pos {
@@ -171,33 +214,29 @@ impl<'a> System<'a> for ItemUseSystem {
                 }
             }
 
-            // Can it pass along confusion? Note the use of scopes to escape from the borrow checker!
-            let mut add_confusion = Vec::new();
-            {
-                let causes_confusion = confused.get(use_item.item);
-                match causes_confusion {
-                    None => {}
-                    Some(confusion) => {
-                        used_item = false;
-                        for mob in targets.iter() {
-                            add_confusion.push((*mob, confusion.turns));
-                            if entity == *player_entity {
-                                let mob_name = names.get(*mob).unwrap();
-                                let item_name = names.get(use_item.item).unwrap();
-                                game_log.entries.push(format!("You use {} on {}, confusing them.", item_name.name, mob_name.name));
-
-                                let pos = positions.get(*mob);
-                                if let Some(pos) = pos {
-                                    particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::MAGENTA), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('?'), 200.0);
-                                }
-                            }
+            // Status effects: confusion scrolls, haste potions and the like
+            if let Some(causes) = causes_status.get(use_item.item) {
+                used_item = false;
+                for mob in targets.iter() {
+                    StatusEffects::apply(&mut statuses, *mob, causes.effect);
+                    if entity == *player_entity {
+                        let item_name = names.get(use_item.item).unwrap();
+                        if *mob == *player_entity {
+                            game_log.entries.push(format!("You use {}. You are {}!", item_name.name, causes.effect.kind.name().to_lowercase()));
+                        } else {
+                            let mob_name = names.get(*mob).unwrap();
+                            game_log.entries.push(format!("You use {} on {}. It is {}!", item_name.name, mob_name.name, causes.effect.kind.name().to_lowercase()));
                         }
                     }
+
+                    if let Some(pos) = positions.get(*mob) {
+                        let (fg, glyph) = status_particle(causes.effect.kind);
+                        particle_builder.request(pos.x, pos.y, fg, rltk::RGB::named(rltk::BLACK), glyph, 200.0);
+                    }
+
+                    used_item = true;
                 }
             }
-            // for mob in add_confusion.iter() {
-            //     confused.insert(mob.0, Confusion { turns: mob.1 }).expect("Unable to insert status");
-            // }
 
             // If its a consumable, we delete it on use
             if used_item {
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use crate::components::{BlocksTile, Position};
+use crate::components::{BlocksTile, BlocksVisibility, Door, KnownToMonsters, Position};
 use crate::map::Map;
 
 pub struct MapIndexingSystem {}
@@ -9,14 +9,20 @@ impl<'a> System<'a> for MapIndexingSystem {
         WriteExpect<'a, Map>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, BlocksTile>,
+        ReadStorage<'a, BlocksVisibility>,
+        ReadStorage<'a, Door>,
+        ReadStorage<'a, KnownToMonsters>,
         Entities<'a>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, position, blockers, entities) = data;
+        let (mut map, position, blockers, view_blockers, doors, known_to_monsters, entities) = data;
 
         map.populate_blocked_by_terrain();
         map.clear_content_index();
+        map.view_blocked.clear();
+        map.closed_doors.clear();
+        map.known_traps.clear();
 
         for (entity, position) in (&entities, &position).join() {
             let idx = map.xy_idx(position.x, position.y);
@@ -24,6 +30,15 @@ impl<'a> System<'a> for MapIndexingSystem {
             if let Some(_p) = blockers.get(entity) {
                 map.blocked[idx] = true;
             }
+            if let Some(_v) = view_blockers.get(entity) {
+                map.view_blocked.insert(idx);
+            }
+            if let Some(door) = doors.get(entity) {
+                if !door.open { map.closed_doors.insert(idx); }
+            }
+            if known_to_monsters.get(entity).is_some() {
+                map.known_traps.insert(idx);
+            }
 
             map.tile_content[idx].push(entity);
         }
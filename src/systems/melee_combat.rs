@@ -1,8 +1,14 @@
-use crate::components::{CombatStats, DefenseBonus, Equipped, MeleePowerBonus, Name, Position, SufferDamage, WantsToMelee};
+use crate::components::{Attributes, CombatStats, DefenseBonus, Equipped, MeleeWeapon, Name, Position, Skills, SufferDamage, WantsToMelee};
 use specs::prelude::*;
+use rltk::RandomNumberGenerator;
 use crate::game_log::GameLog;
 use crate::systems::particle::ParticleBuilder;
 
+/// What an attack has to beat when the defender has no quickness, skill or armour to speak of.
+const BASE_ARMOR_CLASS: i32 = 10;
+/// Fists, claws and whatever else hits when nothing better is equipped.
+const UNARMED: MeleeWeapon = MeleeWeapon { damage_n_dice: 1, damage_die_type: 3, damage_bonus: 0, hit_bonus: 0 };
+
 pub struct MeleeCombatSystem {}
 
 impl<'a> System<'a> for MeleeCombatSystem {
@@ -14,50 +20,66 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, Name>,
         ReadStorage<'a, CombatStats>,
         WriteStorage<'a, SufferDamage>,
-        ReadStorage<'a, MeleePowerBonus>,
+        ReadStorage<'a, MeleeWeapon>,
         ReadStorage<'a, DefenseBonus>,
         ReadStorage<'a, Equipped>,
         WriteExpect<'a, ParticleBuilder>,
         ReadStorage<'a, Position>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
+        WriteExpect<'a, RandomNumberGenerator>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (entities, mut game_log, mut wants_melee, names, combat_stats, mut inflict_damage,
-            melee_power_bonuses, defense_bonuses, equipped, mut particle_builder, positions) = data;
+            melee_weapons, defense_bonuses, equipped, mut particle_builder, positions,
+            attributes, skills, mut rng) = data;
 
         for (entity, wants_melee, name, stats) in (&entities, &wants_melee, &names, &combat_stats).join() {
             if stats.hp > 0 {
-                let mut offensive_bonus = 0;
-                for (_item_entity, power_bonus, equipped_by) in (&entities, &melee_power_bonuses, &equipped).join() {
-                    if equipped_by.owner == entity {
-                        offensive_bonus += power_bonus.power;
-                    }
-                }
+                let weapon = (&melee_weapons, &equipped).join()
+                    .find(|(_weapon, equipped_by)| equipped_by.owner == entity)
+                    .map_or(&UNARMED, |(weapon, _equipped_by)| weapon);
 
                 let target_stats = combat_stats.get(wants_melee.target).unwrap();
                 if target_stats.hp > 0 {
                     let target_name = names.get(wants_melee.target).unwrap();
 
-                    let mut defensive_bonus = 0;
-                    for (_item_entity, defense_bonus, equipped_by) in (&entities, &defense_bonuses, &equipped).join() {
-                        if equipped_by.owner == wants_melee.target {
-                            defensive_bonus += defense_bonus.defense;
-                        }
-                    }
+                    let might_bonus = attributes.get(entity).map_or(0, |attr| Attributes::bonus(attr.might));
+                    let melee_skill = skills.get(entity).map_or(0, |skill| skill.melee);
 
+                    // A natural 1 always misses and a natural 20 always hits, whatever the odds
+                    let natural_roll = rng.roll_dice(1, 20);
+                    let to_hit = natural_roll + might_bonus + melee_skill + weapon.hit_bonus;
+                    let armor_class = armor_class(wants_melee.target, &attributes, &skills, &defense_bonuses, &equipped);
                     let pos = positions.get(wants_melee.target);
-                    if let Some(pos) = pos {
-                        particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 150.0);
-                    }
 
-                    let damage = i32::max(0, (stats.power + offensive_bonus) - (target_stats.defense + defensive_bonus));
+                    if natural_roll == 1 || (natural_roll != 20 && to_hit < armor_class) {
+                        if natural_roll == 1 {
+                            game_log.entries.push(format!("{} fumbles the attack on {}!", &name.name, &target_name.name));
+                        } else {
+                            game_log.entries.push(format!("{} attacks {}, but misses.", &name.name, &target_name.name));
+                        }
+                        if let Some(pos) = pos {
+                            particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::CYAN), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 150.0);
+                        }
+                        continue;
+                    }
 
-                    if damage == 0 {
-                        game_log.entries.push(format!("{} is unable to hurt {}", &name.name, &target_name.name));
+                    let mut damage = i32::max(1, rng.roll_dice(weapon.damage_n_dice, weapon.damage_die_type) + weapon.damage_bonus + might_bonus);
+                    if natural_roll == 20 {
+                        damage *= 2;
+                        game_log.entries.push(format!("{} lands a critical hit on {}, for {} hp!", &name.name, &target_name.name, damage));
+                        if let Some(pos) = pos {
+                            particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::RED), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 300.0);
+                        }
                     } else {
                         game_log.entries.push(format!("{} hits {}, for {} hp.", &name.name, &target_name.name, damage));
-                        SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage);
+                        if let Some(pos) = pos {
+                            particle_builder.request(pos.x, pos.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 150.0);
+                        }
                     }
+                    SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage, Some(entity));
                 }
             }
         }
@@ -65,3 +87,15 @@ impl<'a> System<'a> for MeleeCombatSystem {
         wants_melee.clear();
     }
 }
+
+/// What an attack on `target` has to beat: the base, plus its quickness, defense skill and armour.
+pub fn armor_class(target: Entity, attributes: &ReadStorage<Attributes>, skills: &ReadStorage<Skills>,
+                   defense_bonuses: &ReadStorage<DefenseBonus>, equipped: &ReadStorage<Equipped>) -> i32 {
+    let quickness_bonus = attributes.get(target).map_or(0, |attr| Attributes::bonus(attr.quickness));
+    let defense_skill = skills.get(target).map_or(0, |skill| skill.defense);
+    let armor_bonus: i32 = (defense_bonuses, equipped).join()
+        .filter(|(_bonus, equipped_by)| equipped_by.owner == target)
+        .map(|(bonus, _equipped_by)| bonus.defense)
+        .sum();
+    BASE_ARMOR_CLASS + quickness_bonus + defense_skill + armor_bonus
+}
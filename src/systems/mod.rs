@@ -1,7 +1,12 @@
 pub mod damage;
+pub mod hunger;
+pub mod initiative;
 pub mod inventory;
 pub mod map_indexing;
 pub mod melee_combat;
 pub mod monster_ai;
 pub mod save_load;
+pub mod status_effect;
 pub mod particle;
+pub mod ranged_combat;
+pub mod trigger;
@@ -1,9 +1,10 @@
 use specs::prelude::*;
-use crate::components::{Confusion, Monster, Position, Viewshed, WantsToMelee};
-use crate::map::Map;
+use crate::components::{BlocksTile, BlocksVisibility, Door, EntityMoved, Monster, MyTurn, Position, Renderable, StatusEffects, StatusKind, Viewshed, WantsToMelee};
+use crate::map::{open_door, Map};
 use crate::state::RunState;
 use rltk::{Point};
 use crate::systems::particle::ParticleBuilder;
+use crate::systems::status_effect::status_particle;
 
 pub struct MonsterAI {}
 
@@ -19,31 +20,36 @@ impl<'a> System<'a> for MonsterAI {
         ReadStorage<'a, Monster>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, WantsToMelee>,
-        WriteStorage<'a, Confusion>,
+        ReadStorage<'a, StatusEffects>,
         WriteExpect<'a, ParticleBuilder>,
+        WriteStorage<'a, Door>,
+        WriteStorage<'a, BlocksTile>,
+        WriteStorage<'a, BlocksVisibility>,
+        WriteStorage<'a, Renderable>,
+        WriteStorage<'a, EntityMoved>,
+        ReadStorage<'a, MyTurn>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (mut map, player_pos, player_entity, run_state, entities, mut viewshed,
-            monster, mut position, mut wants_to_melee, mut confused, mut particle_builder) = data;
+            monster, mut position, mut wants_to_melee, statuses, mut particle_builder,
+            mut doors, mut blocks_movement, mut blocks_visibility, mut renderables, mut entity_moved, turns) = data;
 
-        if *run_state != RunState::MonsterTurn { return; }
+        if *run_state != RunState::Ticking { return; }
 
-        for (entity, mut viewshed, _monster, mut pos) in (&entities, &mut viewshed, &monster, &mut position).join() {
+        for (entity, viewshed, _monster, pos, _turn) in (&entities, &mut viewshed, &monster, &mut position, &turns).join() {
             let mut can_act = true;
 
-            let is_confused = confused.get_mut(entity);
-            if let Some(i_am_confused) = is_confused {
-                i_am_confused.turns -= 1;
-                if i_am_confused.turns < 1 {
-                    confused.remove(entity);
+            if let Some(status) = statuses.get(entity) {
+                if status.has(StatusKind::Confusion) || status.loses_turn() {
+                    can_act = false;
+                }
+                for kind in [StatusKind::Confusion, StatusKind::Stun] {
+                    if status.has(kind) {
+                        let (fg, glyph) = status_particle(kind);
+                        particle_builder.request(pos.x, pos.y, fg, rltk::RGB::named(rltk::BLACK), glyph, 200.0);
+                    }
                 }
-                can_act = false;
-
-                particle_builder.request(
-                    pos.x, pos.y, rltk::RGB::named(rltk::MAGENTA),
-                    rltk::RGB::named(rltk::BLACK), rltk::to_cp437('?'), 200.0,
-                );
             }
 
             if can_act {
@@ -51,12 +57,19 @@ impl<'a> System<'a> for MonsterAI {
                 if distance < 1.5 {
                     wants_to_melee.insert(entity, WantsToMelee { target: *player_entity }).expect("Unable to insert attack");
                 } else if viewshed.visible_tiles.contains(&*player_pos) {
+                    // Paths avoid known traps, but the player standing on one is still the goal
+                    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+                    let player_on_trap = map.known_traps.remove(&player_idx);
                     let path_to_player = rltk::a_star_search(
                         map.xy_idx(pos.x, pos.y),
-                        map.xy_idx(player_pos.x, player_pos.y),
-                        &mut *map,
+                        player_idx,
+                        &*map,
                     );
-                    if path_to_player.success && path_to_player.steps.len() > 1 {
+                    if player_on_trap { map.known_traps.insert(player_idx); }
+                    if path_to_player.success && path_to_player.steps.len() > 1 && map.closed_doors.contains(&path_to_player.steps[1]) {
+                        // The path leads through a closed door, so spend the turn opening it
+                        open_door(&mut map, path_to_player.steps[1], &mut doors, &mut blocks_movement, &mut blocks_visibility, &mut renderables);
+                    } else if path_to_player.success && path_to_player.steps.len() > 1 {
                         let mut idx = map.xy_idx(pos.x, pos.y);
                         map.blocked[idx] = false;
                         pos.x = path_to_player.steps[1] as i32 % map.width;
@@ -64,6 +77,7 @@ impl<'a> System<'a> for MonsterAI {
                         idx = map.xy_idx(pos.x, pos.y);
                         map.blocked[idx] = true;
                         viewshed.dirty = true;
+                        entity_moved.insert(entity, EntityMoved {}).expect("Unable to insert marker");
                     }
                 }
             }
@@ -0,0 +1,137 @@
+use crate::components::{Ammunition, Attributes, CombatStats, DefenseBonus, Equipped, InBackpack, Item, Name, Position, RangedWeapon, Renderable, SerializeMe, Skills, SufferDamage, WantsToShoot};
+use specs::prelude::*;
+use specs::saveload::{MarkedBuilder, SimpleMarker};
+use rltk::{BaseMap, Point, RandomNumberGenerator};
+use crate::game_log::GameLog;
+use crate::map::Map;
+use crate::systems::melee_combat::armor_class;
+use crate::systems::particle::ParticleBuilder;
+
+pub struct RangedCombatSystem {}
+
+impl<'a> System<'a> for RangedCombatSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, GameLog>,
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, WantsToShoot>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, CombatStats>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, RangedWeapon>,
+        WriteStorage<'a, Ammunition>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, DefenseBonus>,
+        ReadStorage<'a, Equipped>,
+        WriteExpect<'a, ParticleBuilder>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut game_log, map, mut wants_shoot, names, combat_stats, mut inflict_damage,
+            ranged_weapons, mut ammunition, backpack, defense_bonuses, equipped, mut particle_builder,
+            positions, renderables, attributes, skills, mut rng, lazy) = data;
+
+        for (entity, shot, name, shooter_pos) in (&entities, &wants_shoot, &names, &positions).join() {
+            let Some(weapon) = (&ranged_weapons, &equipped).join()
+                .find(|(_weapon, equipped_by)| equipped_by.owner == entity)
+                .map(|(weapon, _equipped_by)| weapon) else { continue };
+            let Some((ammo_entity, ammo, _pack)) = (&entities, &mut ammunition, &backpack).join()
+                .find(|(_ammo_entity, ammo, pack)| pack.owner == entity && ammo.kind == weapon.ammo) else { continue };
+
+            // The shot flies until it meets a wall or something living, and lands where it stops
+            let flight = line_of_fire(&map, Point::new(shooter_pos.x, shooter_pos.y), shot.target);
+            let mut landing = Point::new(shooter_pos.x, shooter_pos.y);
+            let mut victim = None;
+            for tile in flight.iter() {
+                landing = *tile;
+                particle_builder.request(tile.x, tile.y, rltk::RGB::named(rltk::GREY), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('·'), 100.0);
+                let idx = map.xy_idx(tile.x, tile.y);
+                victim = map.tile_content[idx].iter()
+                    .find(|potential_target| combat_stats.get(**potential_target).is_some_and(|stats| stats.hp > 0))
+                    .copied();
+                if victim.is_some() { break; }
+            }
+
+            let ammo_kind = ammo.kind;
+            ammo.count -= 1;
+            if ammo.count < 1 {
+                entities.delete(ammo_entity).expect("Delete failed");
+            }
+
+            let mut recovered = true;
+            match victim {
+                None => {
+                    game_log.entries.push(format!("{} shoots, and hits nothing.", &name.name));
+                }
+                Some(victim) => {
+                    let target_name = names.get(victim).unwrap();
+                    let quickness_bonus = attributes.get(entity).map_or(0, |attr| Attributes::bonus(attr.quickness));
+                    let ranged_skill = skills.get(entity).map_or(0, |skill| skill.ranged);
+
+                    // Aiming is down to quickness, where swinging a sword is down to might
+                    let natural_roll = rng.roll_dice(1, 20);
+                    let to_hit = natural_roll + quickness_bonus + ranged_skill + weapon.hit_bonus;
+                    let armor_class = armor_class(victim, &attributes, &skills, &defense_bonuses, &equipped);
+
+                    if natural_roll == 1 || (natural_roll != 20 && to_hit < armor_class) {
+                        if natural_roll == 1 {
+                            game_log.entries.push(format!("{} fumbles the shot at {}!", &name.name, &target_name.name));
+                        } else {
+                            game_log.entries.push(format!("{} shoots at {}, but misses.", &name.name, &target_name.name));
+                        }
+                        particle_builder.request(landing.x, landing.y, rltk::RGB::named(rltk::CYAN), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 150.0);
+                    } else {
+                        recovered = false;
+                        let mut damage = i32::max(1, rng.roll_dice(weapon.damage_n_dice, weapon.damage_die_type) + weapon.damage_bonus);
+                        if natural_roll == 20 {
+                            damage *= 2;
+                            game_log.entries.push(format!("{} lands a critical shot on {}, for {} hp!", &name.name, &target_name.name, damage));
+                            particle_builder.request(landing.x, landing.y, rltk::RGB::named(rltk::RED), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 300.0);
+                        } else {
+                            game_log.entries.push(format!("{} shoots {}, for {} hp.", &name.name, &target_name.name, damage));
+                            particle_builder.request(landing.x, landing.y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 150.0);
+                        }
+                        SufferDamage::new_damage(&mut inflict_damage, victim, damage, Some(entity));
+                    }
+                }
+            }
+
+            // A shot that hit nothing can be picked up again from where it fell
+            if recovered {
+                let mut builder = lazy.create_entity(&entities)
+                    .with(Position { x: landing.x, y: landing.y })
+                    .with(Item {})
+                    .with(Ammunition { kind: ammo_kind, count: 1 });
+                if let Some(ammo_name) = names.get(ammo_entity) {
+                    builder = builder.with(Name { name: ammo_name.name.clone() });
+                }
+                if let Some(render) = renderables.get(ammo_entity) {
+                    builder = builder.with(Renderable { glyph: render.glyph, fg: render.fg, bg: render.bg, render_order: render.render_order });
+                }
+                builder.marked::<SimpleMarker<SerializeMe>>().build();
+            }
+        }
+
+        wants_shoot.clear();
+    }
+}
+
+/// The tiles a shot from `from` at `to` passes through, stopping short of the first wall or
+/// closed door. The shooter's own tile isn't included.
+pub fn line_of_fire(map: &Map, from: Point, to: Point) -> Vec<Point> {
+    let mut flight = Vec::new();
+    for tile in rltk::line2d(rltk::LineAlg::Bresenham, from, to).into_iter().skip(1) {
+        if tile.x < 0 || tile.x >= map.width || tile.y < 0 || tile.y >= map.height { break; }
+        if map.is_opaque(map.xy_idx(tile.x, tile.y)) { break; }
+        flight.push(tile);
+        if tile == to { break; }
+    }
+    flight
+}
@@ -4,14 +4,17 @@ use std::fs::File;
 use std::path::Path;
 use std::fs;
 use crate::components::SerializeMe;
+use crate::dungeon::MasterDungeonMap;
+use crate::game_log::GameLog;
+use crate::game_seed::GameSeed;
 use crate::map;
 use crate::components::*;
-use specs::error::NoError;
+use std::convert::Infallible;
 
 macro_rules! serialize_individually {
     ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
         $(
-        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
+        SerializeComponents::<Infallible, SimpleMarker<SerializeMe>>::serialize(
             &( $ecs.read_storage::<$type>(), ),
             &$data.0,
             &$data.1,
@@ -29,9 +32,12 @@ pub fn save_game(_ecs: &mut World) {}
 pub fn save_game(ecs: &mut World) {
     // Create helper
     let mapcopy = ecs.get_mut::<map::Map>().unwrap().clone();
+    let dungeon_master = ecs.get_mut::<MasterDungeonMap>().unwrap().clone();
+    let seed = ecs.fetch::<GameSeed>().seed;
+    let rng = (*ecs.fetch::<rltk::RandomNumberGenerator>()).clone();
     let savehelper = ecs
         .create_entity()
-        .with(SerializationHelper { map: mapcopy })
+        .with(SerializationHelper { map: mapcopy, dungeon_master, seed, rng })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
@@ -43,10 +49,14 @@ pub fn save_game(ecs: &mut World) {
         let mut serializer = serde_json::Serializer::new(writer);
         serialize_individually!(
             ecs, serializer, data, Position, Renderable, Player, Viewshed, Monster,
-            Name, BlocksTile, CombatStats, SufferDamage, WantsToMelee, Item, Consumable,
-            Ranged, InflictsDamage, AreaOfEffect, Confusion, ProvidesHealing, InBackpack,
+            Name, BlocksTile, CombatStats, WantsToMelee, Item, Consumable,
+            Ranged, InflictsDamage, AreaOfEffect, ProvidesHealing, InBackpack,
             WantsToPickupItem, WantsToUseItem, WantsToDropItem, SerializationHelper, Equippable,
-            MeleePowerBonus, DefenseBonus, WantsToRemoveItem
+            MeleeWeapon, DefenseBonus, WantsToRemoveItem, OtherLevelPosition, BlocksVisibility,
+            Door, Hidden, EntryTrigger, EntityMoved, SingleActivation, TeleportsVictim, HungerClock,
+            ProvidesFood, Experience, GrantsExperience, Attributes, Skills,
+            CausesStatus, StatusEffects, Initiative, MyTurn, SpeedPenalty,
+            RangedWeapon, Ammunition, WantsToShoot, KnownToMonsters
         );
     }
 
@@ -61,7 +71,7 @@ pub fn does_save_exist() -> bool {
 macro_rules! deserialize_individually {
     ($ecs:expr, $de:expr, $data:expr, $( $type:ty),*) => {
         $(
-        DeserializeComponents::<NoError, _>::deserialize(
+        DeserializeComponents::<Infallible, _>::deserialize(
             &mut ( &mut $ecs.write_storage::<$type>(), ),
             &$data.0, // entities
             &mut $data.1, // marker
@@ -74,16 +84,8 @@ macro_rules! deserialize_individually {
 }
 
 pub fn load_game(ecs: &mut World) {
-    {
-        // Delete everything
-        let mut to_delete = Vec::new();
-        for e in ecs.entities().join() {
-            to_delete.push(e);
-        }
-        for del in to_delete.iter() {
-            ecs.delete_entity(*del).expect("Deletion failed");
-        }
-    }
+    // Load into an empty world, the same as a new game starts from
+    *ecs = crate::create_world();
 
     let data = fs::read_to_string("./savegame.json").unwrap();
     let mut de = serde_json::Deserializer::from_str(&data);
@@ -93,33 +95,41 @@ pub fn load_game(ecs: &mut World) {
 
         deserialize_individually!(
             ecs, de, d, Position, Renderable, Player, Viewshed, Monster, Name, BlocksTile,
-            CombatStats, SufferDamage, WantsToMelee, Item, Consumable, Ranged, InflictsDamage,
-            AreaOfEffect, Confusion, ProvidesHealing, InBackpack, WantsToPickupItem, WantsToUseItem,
-            WantsToDropItem, SerializationHelper, Equippable, MeleePowerBonus, DefenseBonus,
-            WantsToRemoveItem
+            CombatStats, WantsToMelee, Item, Consumable, Ranged, InflictsDamage,
+            AreaOfEffect, ProvidesHealing, InBackpack, WantsToPickupItem, WantsToUseItem,
+            WantsToDropItem, SerializationHelper, Equippable, MeleeWeapon, DefenseBonus,
+            WantsToRemoveItem, OtherLevelPosition, BlocksVisibility, Door, Hidden, EntryTrigger,
+            EntityMoved, SingleActivation, TeleportsVictim, HungerClock, ProvidesFood,
+            Experience, GrantsExperience, Attributes, Skills,
+            CausesStatus, StatusEffects, Initiative, MyTurn, SpeedPenalty,
+            RangedWeapon, Ammunition, WantsToShoot, KnownToMonsters
         );
     }
 
-    let mut deleteme: Option<Entity> = None;
-    {
+    let (helper_entity, helper) = {
+        let entities = ecs.entities();
+        let helpers = ecs.read_storage::<SerializationHelper>();
+        let (e, h) = (&entities, &helpers).join().next().expect("Save has no game data");
+        (e, h.clone())
+    };
+    let mut worldmap = helper.map;
+    worldmap.tile_content = vec![Vec::new(); (worldmap.width * worldmap.height) as usize];
+    ecs.insert(worldmap);
+    ecs.insert(helper.dungeon_master);
+    ecs.insert(GameSeed { seed: helper.seed });
+    ecs.insert(helper.rng);
+    ecs.insert(GameLog { entries: vec!["Welcome back to Rusty Roguelike".to_string()] });
+    ecs.delete_entity(helper_entity).expect("Unable to delete helper");
+
+    let (player_entity, player_pos) = {
         let entities = ecs.entities();
-        let helper = ecs.read_storage::<SerializationHelper>();
         let player = ecs.read_storage::<Player>();
         let position = ecs.read_storage::<Position>();
-        for (e, h) in (&entities, &helper).join() {
-            let mut worldmap = ecs.write_resource::<map::Map>();
-            *worldmap = h.map.clone();
-            worldmap.tile_content = vec![Vec::new(); map::MAP_CELL_COUNT];
-            deleteme = Some(e);
-        }
-        for (e, _p, pos) in (&entities, &player, &position).join() {
-            let mut ppos = ecs.write_resource::<rltk::Point>();
-            *ppos = rltk::Point::new(pos.x, pos.y);
-            let mut player_resource = ecs.write_resource::<Entity>();
-            *player_resource = e;
-        }
-    }
-    ecs.delete_entity(deleteme.unwrap()).expect("Unable to delete helper");
+        let (e, _p, pos) = (&entities, &player, &position).join().next().expect("Save has no player");
+        (e, rltk::Point::new(pos.x, pos.y))
+    };
+    ecs.insert(player_pos);
+    ecs.insert(player_entity);
 }
 
 pub fn delete_save() {
@@ -0,0 +1,94 @@
+use specs::prelude::*;
+use rltk::RGB;
+use crate::components::{CombatStats, MyTurn, Name, Position, StatusEffects, StatusKind, SufferDamage};
+use crate::game_log::GameLog;
+use crate::state::RunState;
+use crate::systems::initiative::is_taking_turn;
+use crate::systems::particle::ParticleBuilder;
+
+/// Applies poison and regeneration and counts status effects down, once per turn of whoever has them.
+pub struct StatusEffectSystem {}
+
+impl<'a> System<'a> for StatusEffectSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+        ReadStorage<'a, MyTurn>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut statuses, mut combat_stats, mut suffer_damage, names, positions,
+            player_entity, run_state, mut log, mut particle_builder, turns) = data;
+
+        let mut cured: Vec<Entity> = Vec::new();
+        for (entity, status) in (&entities, &mut statuses).join() {
+            if !is_taking_turn(entity, *player_entity, *run_state, &turns) { continue; }
+            let is_player = entity == *player_entity;
+
+            for effect in status.effects.iter() {
+                match effect.kind {
+                    StatusKind::Poison => {
+                        SufferDamage::new_damage(&mut suffer_damage, entity, effect.magnitude, None);
+                        if is_player {
+                            log.entries.push(format!("The poison burns you for {} hp.", effect.magnitude));
+                        }
+                    }
+                    StatusKind::Regeneration => {
+                        if let Some(stats) = combat_stats.get_mut(entity) {
+                            stats.hp = i32::min(stats.max_hp, stats.hp + effect.magnitude);
+                        }
+                    }
+                    _ => {}
+                }
+                if let (Some(pos), Some((fg, glyph))) = (positions.get(entity), tick_particle(effect.kind)) {
+                    particle_builder.request(pos.x, pos.y, fg, RGB::named(rltk::BLACK), glyph, 200.0);
+                }
+            }
+
+            for kind in status.count_down() {
+                if is_player {
+                    log.entries.push(format!("You are no longer {}.", kind.name().to_lowercase()));
+                } else if let Some(name) = names.get(entity) {
+                    log.entries.push(format!("{} is no longer {}.", name.name, kind.name().to_lowercase()));
+                }
+            }
+
+            if status.effects.is_empty() {
+                cured.push(entity);
+            }
+        }
+
+        for entity in cured {
+            statuses.remove(entity);
+        }
+    }
+}
+
+/// The colour and glyph shown over something when an effect takes hold of it.
+pub fn status_particle(kind: StatusKind) -> (RGB, rltk::FontCharType) {
+    match kind {
+        StatusKind::Confusion => (RGB::named(rltk::MAGENTA), rltk::to_cp437('?')),
+        StatusKind::Poison => (RGB::named(rltk::GREEN), rltk::to_cp437('♣')),
+        StatusKind::Stun => (RGB::named(rltk::YELLOW), rltk::to_cp437('*')),
+        StatusKind::Haste => (RGB::named(rltk::CYAN), rltk::to_cp437('»')),
+        StatusKind::Slow => (RGB::named(rltk::BLUE), rltk::to_cp437('«')),
+        StatusKind::Regeneration => (RGB::named(rltk::GREEN), rltk::to_cp437('♥')),
+    }
+}
+
+/// Only the effects that do something every turn show it every turn.
+fn tick_particle(kind: StatusKind) -> Option<(RGB, rltk::FontCharType)> {
+    match kind {
+        StatusKind::Poison | StatusKind::Regeneration => Some(status_particle(kind)),
+        _ => None,
+    }
+}
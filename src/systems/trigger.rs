@@ -0,0 +1,124 @@
+use specs::prelude::*;
+use rltk::{Point, RandomNumberGenerator};
+use crate::components::{CausesStatus, EntityMoved, EntryTrigger, Hidden, InflictsDamage, KnownToMonsters, Monster, Name, Position, SingleActivation, StatusEffects, SufferDamage, TeleportsVictim, Viewshed};
+use crate::game_log::GameLog;
+use crate::map::{Map, TileType};
+use crate::systems::particle::ParticleBuilder;
+use crate::systems::status_effect::status_particle;
+
+/// Fires the entry triggers on every tile something moved onto this turn.
+pub struct TriggerSystem {}
+
+impl<'a> System<'a> for TriggerSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, EntityMoved>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, EntryTrigger>,
+        WriteStorage<'a, Hidden>,
+        ReadStorage<'a, Name>,
+        Entities<'a>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, InflictsDamage>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, SingleActivation>,
+        ReadStorage<'a, TeleportsVictim>,
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, Point>,
+        ReadStorage<'a, CausesStatus>,
+        WriteStorage<'a, StatusEffects>,
+        ReadStorage<'a, Monster>,
+        WriteStorage<'a, KnownToMonsters>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut map, mut entity_moved, mut position, entry_trigger, mut hidden, names, entities,
+            mut log, inflicts_damage, mut suffer_damage, single_activation, teleports, mut viewsheds,
+            mut particle_builder, mut rng, player_entity, mut player_pos, causes_status, mut statuses, monsters, mut known_to_monsters) = data;
+
+        let mut moved: Vec<(Entity, i32, i32)> = Vec::new();
+        for (entity, _moved, pos) in (&entities, &entity_moved, &position).join() {
+            moved.push((entity, pos.x, pos.y));
+        }
+        entity_moved.clear();
+
+        let mut remove_entities: Vec<Entity> = Vec::new();
+        for (entity, x, y) in moved.iter() {
+            let idx = map.xy_idx(*x, *y);
+            let mut teleport = false;
+
+            for entity_id in map.tile_content[idx].iter() {
+                if *entity != *entity_id && entry_trigger.get(*entity_id).is_some() {
+                    // Whatever it was, it isn't hidden any more
+                    hidden.remove(*entity_id);
+
+                    // Any monster that saw it go off, the victim included, will steer clear of it
+                    let trap_pos = Point::new(*x, *y);
+                    let seen_by_monster = monsters.get(*entity).is_some()
+                        || (&monsters, &viewsheds).join().any(|(_monster, viewshed)| viewshed.visible_tiles.contains(&trap_pos));
+                    if seen_by_monster {
+                        known_to_monsters.insert(*entity_id, KnownToMonsters {}).expect("Unable to insert trap knowledge");
+                    }
+
+                    let victim_name = names.get(*entity).map_or("Something", |n| n.name.as_str());
+                    let trap_name = names.get(*entity_id).map_or("a trap", |n| n.name.as_str());
+                    log.entries.push(format!("{} triggers {}!", victim_name, trap_name));
+
+                    if let Some(damage) = inflicts_damage.get(*entity_id) {
+                        particle_builder.request(*x, *y, rltk::RGB::named(rltk::ORANGE), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('‼'), 200.0);
+                        SufferDamage::new_damage(&mut suffer_damage, *entity, damage.damage, None);
+                    }
+
+                    if let Some(causes) = causes_status.get(*entity_id) {
+                        StatusEffects::apply(&mut statuses, *entity, causes.effect);
+                        let (fg, glyph) = status_particle(causes.effect.kind);
+                        particle_builder.request(*x, *y, fg, rltk::RGB::named(rltk::BLACK), glyph, 200.0);
+                    }
+
+                    if teleports.get(*entity_id).is_some() {
+                        particle_builder.request(*x, *y, rltk::RGB::named(rltk::MAGENTA), rltk::RGB::named(rltk::BLACK), rltk::to_cp437('*'), 200.0);
+                        teleport = true;
+                    }
+
+                    if single_activation.get(*entity_id).is_some() {
+                        remove_entities.push(*entity_id);
+                    }
+                }
+            }
+
+            if teleport {
+                let destination = random_open_tile(&map, &mut rng);
+                let (new_x, new_y) = (destination as i32 % map.width, destination as i32 / map.width);
+                map.blocked[idx] = false;
+                map.blocked[destination] = true;
+                if let Some(pos) = position.get_mut(*entity) {
+                    pos.x = new_x;
+                    pos.y = new_y;
+                }
+                if let Some(viewshed) = viewsheds.get_mut(*entity) {
+                    viewshed.dirty = true;
+                }
+                if *entity == *player_entity {
+                    *player_pos = Point::new(new_x, new_y);
+                }
+            }
+        }
+
+        for trap in remove_entities.iter() {
+            entities.delete(*trap).expect("Unable to delete trap");
+        }
+    }
+}
+
+fn random_open_tile(map: &Map, rng: &mut RandomNumberGenerator) -> usize {
+    loop {
+        let idx = (rng.roll_dice(1, map.tiles.len() as i32) - 1) as usize;
+        if map.tiles[idx] == TileType::Floor && !map.blocked[idx] {
+            return idx;
+        }
+    }
+}
@@ -1,6 +1,9 @@
-use crate::components::{Player, Position, Viewshed};
+use crate::components::{Hidden, MyTurn, Name, Player, Position, Viewshed};
+use crate::game_log::GameLog;
 use crate::map::Map;
-use rltk::{field_of_view, Point};
+use crate::state::RunState;
+use crate::systems::initiative::is_taking_turn;
+use rltk::{field_of_view, Point, RandomNumberGenerator};
 use specs::prelude::*;
 
 pub struct VisibilitySystem {}
@@ -12,10 +15,20 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteStorage<'a, Viewshed>,
         WriteStorage<'a, Position>,
         ReadStorage<'a, Player>,
+        WriteStorage<'a, Hidden>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, RunState>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, MyTurn>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, entities, mut viewshed, pos, player) = data;
+        let (mut map, entities, mut viewshed, pos, player, mut hidden, mut rng, mut log, names, run_state, player_entity, turns) = data;
+
+        // The systems run several times a turn, but the player only gets one search per action
+        let searching = is_taking_turn(*player_entity, *player_entity, *run_state, &turns);
 
         for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
             viewshed.visible_tiles.clear();
@@ -24,12 +37,23 @@ impl<'a> System<'a> for VisibilitySystem {
 
             // If this is the player, reveal what they can see
             let p: Option<&Player> = player.get(ent);
-            if let Some(_) = p {
+            if p.is_some() {
                 for t in map.visible_tiles.iter_mut() { *t = false };
                 for vis in viewshed.visible_tiles.iter() {
                     let idx = map.xy_idx(vis.x, vis.y);
                     map.revealed_tiles[idx] = true;
                     map.visible_tiles[idx] = true;
+
+                    // Each hidden thing in view has a small chance of being spotted
+                    if !searching { continue; }
+                    for e in map.tile_content[idx].iter() {
+                        if hidden.get(*e).is_some() && rng.roll_dice(1, 24) == 1 {
+                            if let Some(name) = names.get(*e) {
+                                log.entries.push(format!("You spotted a {}.", name.name));
+                            }
+                            hidden.remove(*e);
+                        }
+                    }
                 }
             }
         }